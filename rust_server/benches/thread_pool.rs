@@ -0,0 +1,127 @@
+use std::{
+    hint::black_box,
+    panic::{self, UnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rust_server::thread_pool::ThreadPool;
+
+const WORKERS: usize = 4;
+const SHORT_JOBS: usize = 200_000;
+const LONG_JOBS: usize = 2_000;
+const LONG_JOB_DURATION: Duration = Duration::from_micros(200);
+
+type Job = Box<dyn FnOnce() + Send + UnwindSafe + 'static>;
+
+// The single shared receiver design the pool used before work stealing, kept as a baseline.
+struct MutexPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl MutexPool {
+    fn new(size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..size)
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    let message = receiver.lock().unwrap().recv();
+                    match message {
+                        Ok(job) => {
+                            if panic::catch_unwind(job).is_err() {
+                                eprintln!("Job on Worker {id} panicked!");
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + UnwindSafe + 'static,
+    {
+        self.sender.as_ref().unwrap().send(Box::new(f)).unwrap();
+    }
+}
+
+impl Drop for MutexPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            worker.join().unwrap();
+        }
+    }
+}
+
+fn short_job(counter: &AtomicUsize) {
+    counter.fetch_add(black_box(1), Ordering::Relaxed);
+}
+
+fn long_job(counter: &AtomicUsize) {
+    let started = Instant::now();
+    while started.elapsed() < LONG_JOB_DURATION {
+        black_box(started);
+    }
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+fn bench<P>(name: &str, pool: P, jobs: usize, execute: impl Fn(&P, Arc<AtomicUsize>)) {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+
+    for _ in 0..jobs {
+        execute(&pool, Arc::clone(&counter));
+    }
+    drop(pool);
+
+    let elapsed = started.elapsed();
+    assert_eq!(jobs, counter.load(Ordering::Relaxed));
+    eprintln!(
+        "{name:<28} {jobs:>8} jobs in {:>9.2?} ({:>12.0} jobs/s)",
+        elapsed,
+        jobs as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    bench(
+        "mutex receiver, short",
+        MutexPool::new(WORKERS),
+        SHORT_JOBS,
+        |pool, counter| pool.execute(move || short_job(&counter)),
+    );
+    bench(
+        "work stealing, short",
+        ThreadPool::new(WORKERS),
+        SHORT_JOBS,
+        |pool, counter| pool.execute(move || short_job(&counter)).unwrap(),
+    );
+    bench(
+        "mutex receiver, long",
+        MutexPool::new(WORKERS),
+        LONG_JOBS,
+        |pool, counter| pool.execute(move || long_job(&counter)),
+    );
+    bench(
+        "work stealing, long",
+        ThreadPool::new(WORKERS),
+        LONG_JOBS,
+        |pool, counter| pool.execute(move || long_job(&counter)).unwrap(),
+    );
+}
@@ -0,0 +1,321 @@
+use std::io::{self, Cursor, Read};
+
+use flate2::read::{DeflateEncoder, GzEncoder};
+
+use crate::{
+    http::{negotiate_encoding, ContentCoding, HttpEntity, HttpRequest, HttpResponse, HttpStatus},
+    router::{Middleware, Next},
+};
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+pub struct Compression {
+    encodings: Vec<ContentCoding>,
+    min_size: u64,
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Self {
+            encodings: vec![
+                ContentCoding::Brotli,
+                ContentCoding::Gzip,
+                ContentCoding::Deflate,
+            ],
+            min_size: 1024,
+        }
+    }
+
+    pub fn encodings(&mut self, encodings: &[ContentCoding]) {
+        self.encodings = encodings.to_vec();
+    }
+
+    pub fn min_size(&mut self, min_size: u64) {
+        self.min_size = min_size;
+    }
+
+    fn compress(&self, response: &mut HttpResponse, coding: Option<ContentCoding>) {
+        if response.status != HttpStatus::Ok
+            || response.entity.is_none()
+            || response.headers.get("Content-Encoding").is_some()
+        {
+            return;
+        }
+        let Some(content_type) = response
+            .headers
+            .get_first("Content-Type")
+            .filter(|content_type| is_compressible(content_type))
+            .map(str::to_owned)
+        else {
+            return;
+        };
+
+        response.headers.put_list_item("Vary", "Accept-Encoding");
+        let Some(coding) = coding else {
+            return;
+        };
+        if response
+            .entity
+            .as_ref()
+            .and_then(HttpEntity::len)
+            .is_some_and(|length| length < self.min_size)
+        {
+            return;
+        }
+
+        let entity = match encode(response.entity.take().unwrap(), coding) {
+            Ok(entity) => entity,
+            Err(e) => {
+                eprintln!("Error compressing response: {e}");
+                *response = HttpResponse::new(HttpStatus::InternalServerError);
+                return;
+            }
+        };
+        response.set_entity(entity, &content_type);
+        response
+            .headers
+            .set_all("Content-Encoding", &[coding.token()]);
+        response.headers.remove("Accept-Ranges");
+
+        // The compressed bytes differ from the identity representation, so
+        // only a weak validator still holds.
+        if let Some(etag) = response.headers.get_first("ETag") {
+            if !etag.starts_with("W/") {
+                let weak = format!("W/{etag}");
+                response.headers.set_all("ETag", &[&weak]);
+            }
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware for Compression {
+    fn handle(&self, request: &mut HttpRequest, next: Next<'_>) -> HttpResponse {
+        let coding = negotiate_encoding(&request.headers, &self.encodings);
+        let mut response = next.run(request);
+        self.compress(&mut response, coding);
+        response
+    }
+}
+
+pub fn is_compressible(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    media_type.starts_with("text/")
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+        || matches!(
+            media_type.as_str(),
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/wasm"
+                | "image/svg+xml"
+        )
+}
+
+fn encode(entity: HttpEntity, coding: ContentCoding) -> io::Result<HttpEntity> {
+    match entity {
+        HttpEntity::Bytes(bytes) => {
+            let mut compressed = Vec::new();
+            encoder(Box::new(Cursor::new(bytes)), coding).read_to_end(&mut compressed)?;
+            Ok(HttpEntity::Bytes(compressed))
+        }
+        HttpEntity::Reader { reader, .. } => Ok(HttpEntity::Reader {
+            reader: encoder(reader, coding),
+            length: None,
+        }),
+        HttpEntity::Chunks(chunks) => Ok(HttpEntity::Reader {
+            reader: encoder(Box::new(ChunkReader::new(chunks)), coding),
+            length: None,
+        }),
+    }
+}
+
+fn encoder(reader: Box<dyn Read + Send>, coding: ContentCoding) -> Box<dyn Read + Send> {
+    let level = flate2::Compression::default();
+    match coding {
+        ContentCoding::Brotli => Box::new(brotli::CompressorReader::new(
+            reader,
+            BROTLI_BUFFER_SIZE,
+            BROTLI_QUALITY,
+            BROTLI_WINDOW,
+        )),
+        ContentCoding::Gzip => Box::new(GzEncoder::new(reader, level)),
+        ContentCoding::Deflate => Box::new(DeflateEncoder::new(reader, level)),
+    }
+}
+
+struct ChunkReader {
+    chunks: Box<dyn Iterator<Item = Vec<u8>> + Send>,
+    current: Cursor<Vec<u8>>,
+}
+
+impl ChunkReader {
+    fn new(chunks: Box<dyn Iterator<Item = Vec<u8>> + Send>) -> Self {
+        Self {
+            chunks,
+            current: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.current = Cursor::new(chunk),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Headers;
+    use flate2::read::{DeflateDecoder, GzDecoder};
+
+    const TEXT: &str = "All work and no play makes Jack a dull boy. ";
+
+    fn request(accept_encoding: Option<&str>) -> HttpRequest {
+        let mut raw = String::from("GET / HTTP/1.1\r\n");
+        if let Some(accept_encoding) = accept_encoding {
+            raw.push_str(&format!("Accept-Encoding: {accept_encoding}\r\n"));
+        }
+        raw.push_str("\r\n");
+        HttpRequest::build(&mut raw.as_bytes()).unwrap()
+    }
+
+    fn respond(
+        compression: &Compression,
+        accept_encoding: Option<&str>,
+        response: HttpResponse,
+    ) -> HttpResponse {
+        let response = std::sync::Mutex::new(Some(response));
+        let endpoint = |_: &mut HttpRequest| response.lock().unwrap().take().unwrap();
+        compression.handle(&mut request(accept_encoding), Next::new(&[], &endpoint))
+    }
+
+    fn text_response(repeat: usize) -> HttpResponse {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity(&TEXT.repeat(repeat), "text/plain; charset=utf-8");
+        response.headers.put("ETag", "\"abc\"");
+        response
+    }
+
+    fn entity_bytes(response: HttpResponse) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match response.entity.unwrap() {
+            HttpEntity::Bytes(entity) => bytes = entity,
+            HttpEntity::Reader { mut reader, .. } => {
+                reader.read_to_end(&mut bytes).unwrap();
+            }
+            HttpEntity::Chunks(chunks) => chunks.for_each(|chunk| bytes.extend(chunk)),
+        }
+        bytes
+    }
+
+    fn decode(coding: &str, bytes: &[u8]) -> String {
+        let mut decoded = String::new();
+        match coding {
+            "gzip" => GzDecoder::new(bytes).read_to_string(&mut decoded),
+            "deflate" => DeflateDecoder::new(bytes).read_to_string(&mut decoded),
+            "br" => brotli::Decompressor::new(bytes, 4096).read_to_string(&mut decoded),
+            _ => panic!("unexpected coding {coding}"),
+        }
+        .unwrap();
+        decoded
+    }
+
+    #[test]
+    fn test_compresses_with_negotiated_coding() {
+        let compression = Compression::new();
+
+        for (accept_encoding, expected) in [
+            ("gzip, deflate, br", "br"),
+            ("gzip;q=1, br;q=0.5", "gzip"),
+            ("deflate", "deflate"),
+        ] {
+            let response = respond(&compression, Some(accept_encoding), text_response(100));
+            let headers = &response.headers;
+
+            assert_eq!(Some(expected), headers.get_first("Content-Encoding"));
+            assert_eq!(Some("Accept-Encoding"), headers.get_first("Vary"));
+            assert_eq!(Some("W/\"abc\""), headers.get_first("ETag"));
+            let length = headers
+                .get_first("Content-Length")
+                .unwrap()
+                .parse::<usize>()
+                .unwrap();
+            assert!(length < TEXT.len() * 100);
+
+            let body = entity_bytes(response);
+            assert_eq!(length, body.len());
+            assert_eq!(TEXT.repeat(100), decode(expected, &body));
+        }
+    }
+
+    #[test]
+    fn test_skips_ineligible_responses() {
+        let compression = Compression::new();
+
+        let response = respond(&compression, None, text_response(100));
+        assert_eq!(None, response.headers.get_first("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding"), response.headers.get_first("Vary"));
+
+        let response = respond(&compression, Some("gzip"), text_response(1));
+        assert_eq!(None, response.headers.get_first("Content-Encoding"));
+
+        let mut png = HttpResponse::new(HttpStatus::Ok);
+        png.entity(&[0; 4096], "image/png");
+        let response = respond(&compression, Some("gzip"), png);
+        assert_eq!(None, response.headers.get_first("Content-Encoding"));
+        assert_eq!(None, response.headers.get_first("Vary"));
+
+        let mut encoded = text_response(100);
+        encoded.headers.put("Content-Encoding", "br");
+        let response = respond(&compression, Some("gzip"), encoded);
+        assert_eq!(Some("br"), response.headers.get_first("Content-Encoding"));
+    }
+
+    #[test]
+    fn test_compresses_streams() {
+        let compression = Compression::new();
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.chunked_entity(vec![TEXT.as_bytes().to_vec(); 3], "text/plain");
+
+        let response = respond(&compression, Some("gzip"), response);
+        let headers: &Headers = &response.headers;
+        assert_eq!(Some("gzip"), headers.get_first("Content-Encoding"));
+        assert_eq!(None, headers.get_first("Content-Length"));
+        assert_eq!(TEXT.repeat(3), decode("gzip", &entity_bytes(response)));
+    }
+
+    #[test]
+    fn test_is_compressible() {
+        assert!(is_compressible("text/html; charset=utf-8"));
+        assert!(is_compressible("application/json"));
+        assert!(is_compressible("application/problem+json"));
+        assert!(is_compressible("image/svg+xml"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/zip"));
+    }
+}
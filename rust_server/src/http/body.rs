@@ -1,36 +1,492 @@
-use std::io::BufRead;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead, BufReader, Read};
 
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+
+use super::form::{self, FormData, FormError, DEFAULT_SPILL_THRESHOLD};
+#[cfg(feature = "json")]
+use super::json::{self, JsonError};
+use super::spool::{Spool, Spooled};
 use super::Headers;
 
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+// Fields that control framing, routing, authentication or response handling
+// must come from the header section, never from a trailer (RFC 9110 §6.5.1).
+const FORBIDDEN_TRAILERS: [&str; 17] = [
+    "authorization",
+    "cache-control",
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-range",
+    "content-type",
+    "cookie",
+    "expect",
+    "host",
+    "max-forwards",
+    "proxy-authorization",
+    "range",
+    "set-cookie",
+    "te",
+    "trailer",
+    "transfer-encoding",
+];
+
 #[derive(Debug)]
 pub struct HttpBody {
-    raw_body: Vec<u8>,
+    content: Spooled,
+    content_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BodyError {
+    UnsupportedTransferEncoding(String),
+    InvalidContentLength(String),
+    ConflictingLength,
+    TooLarge,
+    MalformedChunkSize(String),
+    MissingChunkTerminator,
+    MalformedTrailer(String),
+    Io(io::Error),
 }
 
 impl HttpBody {
     pub fn new(bytes: &[u8]) -> Self {
         Self {
-            raw_body: Vec::from(bytes),
+            content: Spooled::Memory(Vec::from(bytes)),
+            content_type: None,
         }
     }
 
-    pub fn build(headers: &Headers, buf_reader: &mut dyn BufRead) -> Option<Self> {
-        let raw = headers
-            .get_first("content-length")
-            .and_then(|content_length| content_length.parse::<usize>().ok())
-            .filter(|content_length| *content_length > 0)
-            .and_then(|content_length| {
-                let mut body = vec![0u8; content_length];
-                match buf_reader.read_exact(&mut body) {
-                    Ok(()) => Some(body),
-                    Err(_) => None,
-                }
-            });
+    pub fn with_content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_owned());
+        self
+    }
+
+    pub fn build(
+        headers: &mut Headers,
+        buf_reader: &mut dyn BufRead,
+    ) -> Result<Option<Self>, BodyError> {
+        Self::build_limited(headers, buf_reader, usize::MAX, DEFAULT_SPILL_THRESHOLD)
+    }
+
+    pub fn build_limited(
+        headers: &mut Headers,
+        buf_reader: &mut dyn BufRead,
+        max_size: usize,
+        spill_threshold: usize,
+    ) -> Result<Option<Self>, BodyError> {
+        let content_type = headers.get_first("content-type").map(str::to_owned);
+        let transfer_encoding = headers
+            .get_splitting_commas("transfer-encoding")
+            .map(|encodings| encodings.collect::<Vec<_>>().join(", "));
+
+        if let Some(encoding) = transfer_encoding {
+            // Disagreeing framing is how requests get smuggled past proxies (RFC 9112 §6.3).
+            if headers.get("content-length").is_some() {
+                return Err(BodyError::ConflictingLength);
+            }
+            if !encoding.eq_ignore_ascii_case("chunked") {
+                return Err(BodyError::UnsupportedTransferEncoding(encoding));
+            }
+
+            let mut spool = Spool::new(spill_threshold);
+            read_chunked(headers, buf_reader, max_size, &mut spool)?;
+            return Ok(Some(Self {
+                content: spool.finish().map_err(BodyError::Io)?,
+                content_type,
+            }));
+        }
+
+        let content_length = match headers.get_first("content-length") {
+            Some(value) => parse_content_length(value)?,
+            None => 0,
+        };
+        if content_length == 0 {
+            return Ok(None);
+        }
+
+        if content_length > max_size {
+            return Err(BodyError::TooLarge);
+        }
+
+        // Grow with the data actually received rather than trusting the declared length.
+        let mut spool = Spool::new(spill_threshold);
+        let read = io::copy(&mut buf_reader.take(content_length as u64), &mut spool)
+            .map_err(BodyError::Io)?;
+        if read < content_length as u64 {
+            return Err(BodyError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
 
-        raw.map(|vec| Self::new(&vec))
+        Ok(Some(Self {
+            content: spool.finish().map_err(BodyError::Io)?,
+            content_type,
+        }))
     }
 
     pub fn as_str_lossy(&self) -> String {
-        String::from_utf8_lossy(&self.raw_body).into_owned()
+        match self.content.as_bytes() {
+            Some(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            None => String::from_utf8_lossy(&self.bytes().unwrap_or_default()).into_owned(),
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        self.content.as_bytes()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.content.is_in_memory()
+    }
+
+    pub fn reader(&self) -> io::Result<Box<dyn Read + Send + '_>> {
+        self.content.reader()
+    }
+
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        self.content.bytes()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn form(&self) -> Result<FormData, FormError> {
+        self.form_with_threshold(DEFAULT_SPILL_THRESHOLD)
+    }
+
+    pub fn form_with_threshold(&self, spill_threshold: usize) -> Result<FormData, FormError> {
+        let mut reader = BufReader::new(self.reader().map_err(FormError::Io)?);
+        form::parse_form(&mut reader, self.content_type(), spill_threshold)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        let reader = self.reader().map_err(serde_json::Error::io)?;
+        json::parse_json(reader, self.content_type())
+    }
+}
+
+fn parse_content_length(value: &str) -> Result<usize, BodyError> {
+    let value = value.trim();
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(BodyError::InvalidContentLength(value.to_owned()));
+    }
+
+    // All digits, so a parse failure can only mean overflow.
+    value.parse().map_err(|_| BodyError::TooLarge)
+}
+
+fn read_chunked(
+    headers: &mut Headers,
+    buf_reader: &mut dyn BufRead,
+    max_size: usize,
+    body: &mut Spool,
+) -> Result<(), BodyError> {
+    loop {
+        let line = read_line(buf_reader)?;
+        let size = parse_chunk_size(&line)?;
+
+        if size == 0 {
+            break;
+        }
+        if size as u64 > max_size as u64 - body.len() {
+            return Err(BodyError::TooLarge);
+        }
+
+        let read = io::copy(&mut buf_reader.take(size as u64), body).map_err(BodyError::Io)?;
+        if read < size as u64 {
+            return Err(BodyError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        let mut terminator = [0u8; 2];
+        buf_reader
+            .read_exact(&mut terminator)
+            .map_err(BodyError::Io)?;
+        if &terminator != b"\r\n" {
+            return Err(BodyError::MissingChunkTerminator);
+        }
+    }
+
+    let mut trailer_bytes = 0;
+    loop {
+        let trailer = read_line(buf_reader)?;
+        if trailer.is_empty() {
+            break;
+        }
+
+        trailer_bytes += trailer.len();
+        if trailer_bytes > MAX_LINE_LENGTH {
+            return Err(BodyError::MalformedTrailer(trailer));
+        }
+
+        let Some((key, value)) = trailer
+            .split_once(':')
+            .filter(|(key, _)| !key.trim().is_empty())
+        else {
+            return Err(BodyError::MalformedTrailer(trailer));
+        };
+        let key = key.trim();
+        if !FORBIDDEN_TRAILERS
+            .iter()
+            .any(|name| key.eq_ignore_ascii_case(name))
+        {
+            headers.put(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_chunk_size(line: &str) -> Result<usize, BodyError> {
+    let (size, _extensions) = line.split_once(';').unwrap_or((line, ""));
+    let size = size.trim();
+
+    if size.is_empty() || !size.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(BodyError::MalformedChunkSize(line.to_owned()));
+    }
+
+    usize::from_str_radix(size, 16).map_err(|_| BodyError::MalformedChunkSize(line.to_owned()))
+}
+
+fn read_line(buf_reader: &mut dyn BufRead) -> Result<String, BodyError> {
+    let mut line = Vec::new();
+    buf_reader
+        .take(MAX_LINE_LENGTH as u64)
+        .read_until(b'\n', &mut line)
+        .map_err(BodyError::Io)?;
+
+    if line.len() == MAX_LINE_LENGTH && line.last() != Some(&b'\n') {
+        return Err(BodyError::MalformedChunkSize(
+            String::from_utf8_lossy(&line).into_owned(),
+        ));
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(BodyError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(['\r', '\n']).to_owned())
+        .map_err(|e| {
+            BodyError::MalformedChunkSize(String::from_utf8_lossy(e.as_bytes()).into_owned())
+        })
+}
+
+impl Display for BodyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedTransferEncoding(encoding) => {
+                write!(f, "Unsupported transfer encoding: {encoding}")
+            }
+            Self::TooLarge => f.write_str("Body exceeds the size limit"),
+            Self::InvalidContentLength(value) => write!(f, "Invalid content length: {value:?}"),
+            Self::ConflictingLength => {
+                f.write_str("Both Content-Length and Transfer-Encoding were sent")
+            }
+            Self::MalformedChunkSize(line) => write!(f, "Malformed chunk size: {line:?}"),
+            Self::MissingChunkTerminator => f.write_str("Chunk data not followed by CRLF"),
+            Self::MalformedTrailer(line) => write!(f, "Malformed trailer: {line:?}"),
+            Self::Io(e) => write!(f, "Error reading body: {e}"),
+        }
+    }
+}
+
+impl Error for BodyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunked_headers() -> Headers {
+        let mut headers = Headers::new();
+        headers.put("Transfer-Encoding", "chunked");
+        headers
+    }
+
+    #[test]
+    fn test_content_length() {
+        let mut headers = Headers::new();
+        headers.put("Content-Length", "5");
+
+        let mut reader = "helloGET".as_bytes();
+        let body = HttpBody::build(&mut headers, &mut reader).unwrap().unwrap();
+
+        assert_eq!("hello", body.as_str_lossy());
+        assert_eq!(b"GET", reader);
+    }
+
+    #[test]
+    fn test_form_uses_content_type() {
+        let mut headers = Headers::new();
+        headers.put("Content-Length", "5");
+        headers.put("Content-Type", "application/x-www-form-urlencoded");
+
+        let body = HttpBody::build(&mut headers, &mut "a=1&b".as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("1"), body.form().unwrap().fields.get_first("a"));
+
+        let body = HttpBody::new(b"a=1");
+        assert!(matches!(body.form(), Err(FormError::MissingContentType)));
+    }
+
+    #[test]
+    fn test_large_body_spills_to_temp_file() {
+        let upload = vec![b'x'; 64 * 1024];
+        let mut raw = b"--b\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nhi\r\n\
+            --b\r\nContent-Disposition: form-data; name=\"f\"; filename=\"f.bin\"\r\n\r\n"
+            .to_vec();
+        raw.extend_from_slice(&upload);
+        raw.extend_from_slice(b"\r\n--b--\r\n");
+
+        let mut headers = Headers::new();
+        headers.put("Content-Length", &raw.len().to_string());
+        headers.put("Content-Type", "multipart/form-data; boundary=b");
+
+        let body = HttpBody::build_limited(&mut headers, &mut raw.as_slice(), usize::MAX, 1024)
+            .unwrap()
+            .unwrap();
+        assert!(!body.is_in_memory());
+        assert_eq!(None, body.as_bytes());
+        assert_eq!(raw.len() as u64, body.len());
+
+        let form = body.form_with_threshold(1024).unwrap();
+        assert_eq!(Some("hi"), form.fields.get_first("note"));
+        let file = form.file("f").unwrap();
+        assert!(!file.is_in_memory());
+        assert_eq!(upload, file.bytes().unwrap());
+
+        let mut headers = chunked_headers();
+        let mut reader = "5\r\nhello\r\n0\r\n\r\n".as_bytes();
+        let body = HttpBody::build_limited(&mut headers, &mut reader, usize::MAX, 4)
+            .unwrap()
+            .unwrap();
+        assert!(!body.is_in_memory());
+        assert_eq!("hello", body.as_str_lossy());
+    }
+
+    #[test]
+    fn test_no_body() {
+        let mut headers = Headers::new();
+        let mut reader = "".as_bytes();
+
+        assert!(HttpBody::build(&mut headers, &mut reader)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_chunked() {
+        let mut headers = chunked_headers();
+        let mut reader = "5\r\nhello\r\n7;name=value\r\n, world\r\n0\r\n\r\nNEXT".as_bytes();
+
+        let body = HttpBody::build(&mut headers, &mut reader).unwrap().unwrap();
+
+        assert_eq!("hello, world", body.as_str_lossy());
+        assert_eq!(b"NEXT", reader);
+    }
+
+    #[test]
+    fn test_chunked_trailers() {
+        let mut headers = chunked_headers();
+        let mut reader = "3\r\nabc\r\n0\r\nExpires: never\r\nChecksum: 1a\r\n\r\n".as_bytes();
+
+        let body = HttpBody::build(&mut headers, &mut reader).unwrap().unwrap();
+
+        assert_eq!("abc", body.as_str_lossy());
+        assert_eq!(Some("never"), headers.get_first("expires"));
+        assert_eq!(Some("1a"), headers.get_first("checksum"));
+    }
+
+    #[test]
+    fn test_forbidden_trailers_dropped() {
+        let mut headers = chunked_headers();
+        headers.put("Host", "example.com");
+        let mut reader =
+            "3\r\nabc\r\n0\r\nHost: evil.com\r\nContent-Length: 5\r\nCookie: a=b\r\nExpires: never\r\n\r\n"
+                .as_bytes();
+
+        HttpBody::build(&mut headers, &mut reader).unwrap().unwrap();
+
+        assert_eq!(Some(&["example.com".to_owned()][..]), headers.get("host"));
+        assert_eq!(None, headers.get("content-length"));
+        assert_eq!(None, headers.get("cookie"));
+        assert_eq!(Some("never"), headers.get_first("expires"));
+    }
+
+    #[test]
+    fn test_chunked_rejects_content_length() {
+        let mut headers = chunked_headers();
+        headers.put("Content-Length", "100");
+        let mut reader = "2\r\nhi\r\n0\r\n\r\n".as_bytes();
+
+        assert!(matches!(
+            HttpBody::build(&mut headers, &mut reader),
+            Err(BodyError::ConflictingLength)
+        ));
+    }
+
+    #[test]
+    fn test_malformed_chunk_size() {
+        for raw in ["zz\r\n", "+5\r\nhello\r\n0\r\n\r\n", "\r\n"] {
+            let mut headers = chunked_headers();
+            let result = HttpBody::build(&mut headers, &mut raw.as_bytes());
+
+            assert!(matches!(result, Err(BodyError::MalformedChunkSize(_))));
+        }
+    }
+
+    #[test]
+    fn test_missing_chunk_terminator() {
+        let mut headers = chunked_headers();
+        let result = HttpBody::build(&mut headers, &mut "2\r\nhiXX0\r\n\r\n".as_bytes());
+
+        assert!(matches!(result, Err(BodyError::MissingChunkTerminator)));
+    }
+
+    #[test]
+    fn test_truncated_chunk() {
+        let mut headers = chunked_headers();
+        let result = HttpBody::build(&mut headers, &mut "a\r\nhi".as_bytes());
+
+        assert!(matches!(result, Err(BodyError::Io(_))));
+    }
+
+    #[test]
+    fn test_malformed_trailer() {
+        let mut headers = chunked_headers();
+        let result = HttpBody::build(&mut headers, &mut "0\r\nnot a header\r\n\r\n".as_bytes());
+
+        assert!(matches!(result, Err(BodyError::MalformedTrailer(_))));
+    }
+
+    #[test]
+    fn test_unsupported_transfer_encoding() {
+        let mut headers = Headers::new();
+        headers.put("Transfer-Encoding", "gzip");
+        let result = HttpBody::build(&mut headers, &mut "".as_bytes());
+
+        assert!(matches!(
+            result,
+            Err(BodyError::UnsupportedTransferEncoding(_))
+        ));
     }
 }
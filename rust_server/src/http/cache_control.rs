@@ -0,0 +1,100 @@
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CacheControl {
+    pub public: bool,
+    pub private: bool,
+    pub no_cache: bool,
+    pub no_store: bool,
+    pub no_transform: bool,
+    pub must_revalidate: bool,
+    pub immutable: bool,
+    pub max_age: Option<Duration>,
+    pub s_maxage: Option<Duration>,
+}
+
+impl CacheControl {
+    pub fn no_store() -> Self {
+        Self {
+            no_store: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn revalidate() -> Self {
+        Self {
+            no_cache: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn public(max_age: Duration) -> Self {
+        Self {
+            public: true,
+            max_age: Some(max_age),
+            ..Default::default()
+        }
+    }
+
+    pub fn private(max_age: Duration) -> Self {
+        Self {
+            private: true,
+            max_age: Some(max_age),
+            ..Default::default()
+        }
+    }
+}
+
+impl Display for CacheControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let flags = [
+            (self.public, "public"),
+            (self.private, "private"),
+            (self.no_cache, "no-cache"),
+            (self.no_store, "no-store"),
+            (self.no_transform, "no-transform"),
+            (self.must_revalidate, "must-revalidate"),
+            (self.immutable, "immutable"),
+        ];
+
+        let mut directives = flags
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, name)| name.to_owned())
+            .collect::<Vec<_>>();
+
+        if let Some(max_age) = self.max_age {
+            directives.push(format!("max-age={}", max_age.as_secs()));
+        }
+        if let Some(s_maxage) = self.s_maxage {
+            directives.push(format!("s-maxage={}", s_maxage.as_secs()));
+        }
+
+        f.write_str(&directives.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("no-store", CacheControl::no_store().to_string());
+        assert_eq!(
+            "public, max-age=3600",
+            CacheControl::public(Duration::from_secs(3600)).to_string()
+        );
+
+        let policy = CacheControl {
+            immutable: true,
+            s_maxage: Some(Duration::from_secs(60)),
+            ..CacheControl::private(Duration::from_secs(10))
+        };
+        assert_eq!(
+            "private, immutable, max-age=10, s-maxage=60",
+            policy.to_string()
+        );
+    }
+}
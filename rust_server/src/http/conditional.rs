@@ -0,0 +1,245 @@
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{Headers, HttpMethod, HttpRequest, HttpStatus};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let last_modified = metadata.modified().ok().map(truncate_to_seconds);
+        let etag = last_modified.map(|modified| {
+            let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+            format!("\"{:x}-{:x}\"", since_epoch.as_secs(), metadata.len())
+        });
+
+        Self {
+            etag,
+            last_modified,
+        }
+    }
+
+    pub fn apply(&self, headers: &mut Headers) {
+        if let Some(etag) = &self.etag {
+            headers.set_all("ETag", &[etag]);
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.set_all("Last-Modified", &[&httpdate::fmt_http_date(last_modified)]);
+        }
+    }
+
+    pub fn evaluate(&self, request: &HttpRequest) -> Option<HttpStatus> {
+        let headers = &request.headers;
+        let safe_method = matches!(request.method, HttpMethod::GET | HttpMethod::HEAD);
+
+        if let Some(if_match) = headers.get_splitting_commas("If-Match") {
+            if !self.matches_any(if_match, true) {
+                return Some(HttpStatus::PreconditionFailed);
+            }
+        } else if let Some(since) = header_date(headers, "If-Unmodified-Since") {
+            if self.last_modified.is_some_and(|modified| modified > since) {
+                return Some(HttpStatus::PreconditionFailed);
+            }
+        }
+
+        if let Some(if_none_match) = headers.get_splitting_commas("If-None-Match") {
+            if self.matches_any(if_none_match, false) {
+                return Some(if safe_method {
+                    HttpStatus::NotModified
+                } else {
+                    HttpStatus::PreconditionFailed
+                });
+            }
+        } else if let Some(since) = header_date(headers, "If-Modified-Since") {
+            if safe_method && self.last_modified.is_some_and(|modified| modified <= since) {
+                return Some(HttpStatus::NotModified);
+            }
+        }
+
+        None
+    }
+
+    pub fn if_range_matches(&self, request: &HttpRequest) -> bool {
+        match request.headers.get_first("If-Range") {
+            None => true,
+            Some(tag) if tag.starts_with('"') || tag.starts_with("W/") => self
+                .etag
+                .as_deref()
+                .is_some_and(|etag| etags_match(etag, tag, true)),
+            Some(date) => match (httpdate::parse_http_date(date), self.last_modified) {
+                (Ok(date), Some(last_modified)) => date == last_modified,
+                _ => false,
+            },
+        }
+    }
+
+    fn matches_any<'a>(&self, mut tags: impl Iterator<Item = &'a str>, strong: bool) -> bool {
+        tags.any(|tag| {
+            tag == "*"
+                || self
+                    .etag
+                    .as_deref()
+                    .is_some_and(|etag| etags_match(etag, tag, strong))
+        })
+    }
+}
+
+fn etags_match(a: &str, b: &str, strong: bool) -> bool {
+    let (a_weak, a) = split_weak(a);
+    let (b_weak, b) = split_weak(b);
+
+    a == b && !(strong && (a_weak || b_weak))
+}
+
+fn split_weak(etag: &str) -> (bool, &str) {
+    match etag.strip_prefix("W/") {
+        Some(opaque) => (true, opaque),
+        None => (false, etag),
+    }
+}
+
+fn header_date(headers: &Headers, key: &str) -> Option<SystemTime> {
+    headers
+        .get_first(key)
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+}
+
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(since_epoch.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators() -> Validators {
+        Validators {
+            etag: Some(String::from("\"abc\"")),
+            last_modified: Some(UNIX_EPOCH + Duration::from_secs(1_000_000_000)),
+        }
+    }
+
+    fn evaluate(method: &str, headers: &[(&str, &str)]) -> Option<HttpStatus> {
+        let mut raw = format!("{method} /file HTTP/1.1\r\n");
+        for (key, value) in headers {
+            raw.push_str(&format!("{key}: {value}\r\n"));
+        }
+        raw.push_str("\r\n");
+
+        validators().evaluate(&HttpRequest::build(&mut raw.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut headers = Headers::new();
+        validators().apply(&mut headers);
+
+        assert_eq!(Some("\"abc\""), headers.get_first("ETag"));
+        assert_eq!(
+            Some("Sun, 09 Sep 2001 01:46:40 GMT"),
+            headers.get_first("Last-Modified")
+        );
+    }
+
+    #[test]
+    fn test_no_conditions() {
+        assert_eq!(None, evaluate("GET", &[]));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        assert_eq!(
+            Some(HttpStatus::NotModified),
+            evaluate("GET", &[("If-None-Match", "\"xyz\", W/\"abc\"")])
+        );
+        assert_eq!(
+            Some(HttpStatus::NotModified),
+            evaluate("HEAD", &[("If-None-Match", "*")])
+        );
+        assert_eq!(None, evaluate("GET", &[("If-None-Match", "\"xyz\"")]));
+        assert_eq!(
+            Some(HttpStatus::PreconditionFailed),
+            evaluate("PUT", &[("If-None-Match", "*")])
+        );
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let same = ("If-Modified-Since", "Sun, 09 Sep 2001 01:46:40 GMT");
+        let before = ("If-Modified-Since", "Sun, 09 Sep 2001 01:46:39 GMT");
+
+        assert_eq!(Some(HttpStatus::NotModified), evaluate("GET", &[same]));
+        assert_eq!(None, evaluate("GET", &[before]));
+        assert_eq!(None, evaluate("POST", &[same]));
+        assert_eq!(None, evaluate("GET", &[("If-Modified-Since", "yesterday")]));
+        assert_eq!(
+            None,
+            evaluate("GET", &[same, ("If-None-Match", "\"other\"")])
+        );
+    }
+
+    #[test]
+    fn test_if_match() {
+        assert_eq!(None, evaluate("PUT", &[("If-Match", "\"abc\"")]));
+        assert_eq!(None, evaluate("PUT", &[("If-Match", "*")]));
+        assert_eq!(
+            Some(HttpStatus::PreconditionFailed),
+            evaluate("PUT", &[("If-Match", "W/\"abc\"")])
+        );
+        assert_eq!(
+            Some(HttpStatus::PreconditionFailed),
+            evaluate("PUT", &[("If-Match", "\"xyz\"")])
+        );
+    }
+
+    #[test]
+    fn test_if_range() {
+        let if_range = |value: &str| {
+            let raw = format!("GET /file HTTP/1.1\r\nIf-Range: {value}\r\n\r\n");
+            validators().if_range_matches(&HttpRequest::build(&mut raw.as_bytes()).unwrap())
+        };
+
+        assert!(validators().if_range_matches(
+            &HttpRequest::build(&mut "GET / HTTP/1.1\r\n\r\n".as_bytes()).unwrap()
+        ));
+        assert!(if_range("\"abc\""));
+        assert!(!if_range("W/\"abc\""));
+        assert!(!if_range("\"xyz\""));
+        assert!(if_range("Sun, 09 Sep 2001 01:46:40 GMT"));
+        assert!(!if_range("Sun, 09 Sep 2001 01:46:39 GMT"));
+        assert!(!if_range("garbage"));
+    }
+
+    #[test]
+    fn test_if_unmodified_since() {
+        assert_eq!(
+            Some(HttpStatus::PreconditionFailed),
+            evaluate(
+                "PUT",
+                &[("If-Unmodified-Since", "Sun, 09 Sep 2001 01:46:39 GMT")]
+            )
+        );
+        assert_eq!(
+            None,
+            evaluate(
+                "PUT",
+                &[("If-Unmodified-Since", "Sun, 09 Sep 2001 01:46:40 GMT")]
+            )
+        );
+        assert_eq!(
+            None,
+            evaluate(
+                "PUT",
+                &[
+                    ("If-Match", "\"abc\""),
+                    ("If-Unmodified-Since", "Sun, 09 Sep 2001 01:46:39 GMT")
+                ]
+            )
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Write};
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "signed-cookies")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "signed-cookies")]
+use sha2::Sha256;
+
+use super::request::is_token;
+use super::{percent_decode, Headers};
+
+#[derive(Debug, Default, Clone)]
+pub struct CookieJar {
+    cookies: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieError {
+    InvalidName(String),
+}
+
+#[cfg(feature = "signed-cookies")]
+#[derive(Clone)]
+pub struct CookieKey {
+    secret: Vec<u8>,
+}
+
+impl CookieJar {
+    pub fn parse(headers: &Headers) -> Self {
+        let cookies = headers
+            .get("Cookie")
+            .into_iter()
+            .flatten()
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .unwrap_or(value);
+
+                is_token(name).then(|| (name.to_owned(), value.to_owned()))
+            })
+            .collect();
+
+        Self { cookies }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.raw(name)
+            .map(|value| percent_decode(value).unwrap_or_else(|| value.to_owned()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.raw(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.cookies
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    #[cfg(feature = "signed-cookies")]
+    pub fn get_signed(&self, name: &str, key: &CookieKey) -> Option<String> {
+        let value = self.get(name)?;
+        let (value, signature) = value.rsplit_once('.')?;
+        key.verify(name, value, signature).then(|| value.to_owned())
+    }
+
+    fn raw(&self, name: &str) -> Option<&str> {
+        self.cookies
+            .iter()
+            .find(|(cookie, _)| cookie == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl SetCookie {
+    pub fn new(name: &str, value: &str) -> Result<Self, CookieError> {
+        if !is_token(name) {
+            return Err(CookieError::InvalidName(name.to_owned()));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            value: encode_value(value),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        })
+    }
+
+    pub fn removal(name: &str) -> Result<Self, CookieError> {
+        Ok(Self::new(name, "")?
+            .expires(SystemTime::UNIX_EPOCH)
+            .max_age(Duration::ZERO))
+    }
+
+    #[cfg(feature = "signed-cookies")]
+    pub fn signed(name: &str, value: &str, key: &CookieKey) -> Result<Self, CookieError> {
+        Self::new(name, &format!("{value}.{}", key.sign(name, value)))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_owned());
+        self
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Strict => f.write_str("Strict"),
+            Self::Lax => f.write_str("Lax"),
+            Self::None => f.write_str("None"),
+        }
+    }
+}
+
+impl Display for SetCookie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", httpdate::fmt_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", attribute_value(domain))?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", attribute_value(path))?;
+        }
+        if self.secure || self.same_site == Some(SameSite::None) {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for CookieError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(f, "Invalid cookie name: {name:?}"),
+        }
+    }
+}
+
+impl Error for CookieError {}
+
+#[cfg(feature = "signed-cookies")]
+impl CookieKey {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+
+    fn mac(&self, name: &str, value: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(name.as_bytes());
+        mac.update(b"=");
+        mac.update(value.as_bytes());
+        mac
+    }
+
+    fn sign(&self, name: &str, value: &str) -> String {
+        self.mac(name, value)
+            .finalize()
+            .into_bytes()
+            .iter()
+            .fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+    }
+
+    fn verify(&self, name: &str, value: &str, signature: &str) -> bool {
+        let Some(signature) = decode_hex(signature) else {
+            return false;
+        };
+        self.mac(name, value).verify_slice(&signature).is_ok()
+    }
+}
+
+#[cfg(feature = "signed-cookies")]
+impl std::fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CookieKey(..)")
+    }
+}
+
+#[cfg(feature = "signed-cookies")]
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn encode_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'!' | b'#'..=b'$' | b'&'..=b'+' | b'-'..=b':' | b'<'..=b'[' | b']'..=b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+fn attribute_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| *c != ';' && !c.is_control())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jar() {
+        let mut headers = Headers::new();
+        headers.put(
+            "Cookie",
+            "session=abc123; theme=\"dark\"; bad name=x; empty=",
+        );
+        headers.put("Cookie", "lang=pt%20BR; session=shadowed");
+
+        let jar = CookieJar::parse(&headers);
+        assert_eq!(5, jar.len());
+        assert_eq!(Some(String::from("abc123")), jar.get("session"));
+        assert_eq!(Some(String::from("dark")), jar.get("theme"));
+        assert_eq!(Some(String::new()), jar.get("empty"));
+        assert_eq!(Some(String::from("pt BR")), jar.get("lang"));
+        assert!(!jar.contains("bad name"));
+        assert!(CookieJar::parse(&Headers::new()).is_empty());
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let cookie = SetCookie::new("session", "a b;c")
+            .unwrap()
+            .expires(SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777))
+            .max_age(Duration::from_secs(3600))
+            .domain("example.com")
+            .path("/app")
+            .http_only(true)
+            .same_site(SameSite::Lax);
+
+        assert_eq!(
+            "session=a%20b%3Bc; Expires=Sun, 06 Nov 1994 08:49:37 GMT; Max-Age=3600; \
+            Domain=example.com; Path=/app; HttpOnly; SameSite=Lax",
+            cookie.to_string()
+        );
+        assert_eq!(
+            "id=1; Secure; SameSite=None",
+            SetCookie::new("id", "1")
+                .unwrap()
+                .same_site(SameSite::None)
+                .to_string()
+        );
+        assert_eq!(
+            "id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0",
+            SetCookie::removal("id").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = "50% off, \"today\"";
+        let cookie = SetCookie::new("promo", value).unwrap().to_string();
+
+        let mut headers = Headers::new();
+        headers.put("Cookie", &cookie);
+        assert_eq!(
+            Some(String::from(value)),
+            CookieJar::parse(&headers).get("promo")
+        );
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        assert_eq!(
+            Err(CookieError::InvalidName(String::from("bad name"))),
+            SetCookie::new("bad name", "x")
+        );
+        assert!(SetCookie::removal("").is_err());
+    }
+
+    #[cfg(feature = "signed-cookies")]
+    #[test]
+    fn test_signed_cookies() {
+        let key = CookieKey::new(b"secret");
+        let jar = |cookie: &str| {
+            let mut headers = Headers::new();
+            headers.put("Cookie", cookie);
+            CookieJar::parse(&headers)
+        };
+
+        let cookie = SetCookie::signed("user", "ada.lovelace", &key)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            Some(String::from("ada.lovelace")),
+            jar(&cookie).get_signed("user", &key)
+        );
+
+        let tampered = cookie.replace("ada", "eve");
+        assert_eq!(None, jar(&tampered).get_signed("user", &key));
+        let renamed = cookie.replacen("user", "admin", 1);
+        assert_eq!(None, jar(&renamed).get_signed("admin", &key));
+        assert_eq!(
+            None,
+            jar(&cookie).get_signed("user", &CookieKey::new(b"other"))
+        );
+        assert!(SetCookie::signed("bad;name", "x", &key).is_err());
+        assert_eq!(None, jar("user=ada").get_signed("user", &key));
+    }
+}
@@ -0,0 +1,85 @@
+use super::negotiation::parse_quality_list;
+use super::Headers;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCoding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl ContentCoding {
+    pub fn token(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gz",
+            Self::Deflate => "zz",
+        }
+    }
+
+    fn matches(&self, token: &str) -> bool {
+        token.eq_ignore_ascii_case(self.token())
+            || (*self == Self::Gzip && token.eq_ignore_ascii_case("x-gzip"))
+    }
+}
+
+pub fn negotiate_encoding(headers: &Headers, supported: &[ContentCoding]) -> Option<ContentCoding> {
+    let accepted = parse_quality_list(headers.get_splitting_commas("Accept-Encoding")?);
+    let wildcard = accepted
+        .iter()
+        .find(|(token, _)| *token == "*")
+        .map(|(_, quality)| *quality);
+
+    let mut best: Option<(ContentCoding, f32)> = None;
+    for coding in supported {
+        let quality = accepted
+            .iter()
+            .find(|(token, _)| coding.matches(token))
+            .map(|(_, quality)| *quality)
+            .or(wildcard)
+            .unwrap_or(0.0);
+
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((*coding, quality));
+        }
+    }
+
+    best.map(|(coding, _)| coding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [ContentCoding; 3] = [
+        ContentCoding::Brotli,
+        ContentCoding::Gzip,
+        ContentCoding::Deflate,
+    ];
+
+    fn negotiate(accept_encoding: &str) -> Option<ContentCoding> {
+        let mut headers = Headers::new();
+        headers.put("Accept-Encoding", accept_encoding);
+        negotiate_encoding(&headers, &ALL)
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(Some(ContentCoding::Brotli), negotiate("gzip, deflate, br"));
+        assert_eq!(Some(ContentCoding::Gzip), negotiate("br;q=0.5, gzip"));
+        assert_eq!(Some(ContentCoding::Gzip), negotiate("x-gzip"));
+        assert_eq!(Some(ContentCoding::Deflate), negotiate("deflate, br;q=0"));
+        assert_eq!(Some(ContentCoding::Gzip), negotiate("*;q=0.3, br;q=0.2"));
+        assert_eq!(None, negotiate("identity"));
+        assert_eq!(None, negotiate("*;q=0"));
+        assert_eq!(None, negotiate_encoding(&Headers::new(), &ALL));
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt::{Debug, Formatter};
+use std::io::{self, Read, Write};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+pub enum HttpEntity {
+    Bytes(Vec<u8>),
+    Reader {
+        reader: Box<dyn Read + Send>,
+        length: Option<u64>,
+    },
+    Chunks(Box<dyn Iterator<Item = Vec<u8>> + Send>),
+}
+
+impl HttpEntity {
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes.len() as u64),
+            Self::Reader { length, .. } => *length,
+            Self::Chunks(_) => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn write_to(self, stream: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Self::Bytes(bytes) => stream.write_all(&bytes),
+            Self::Reader {
+                reader,
+                length: Some(length),
+            } => {
+                let copied = io::copy(&mut reader.take(length), stream)?;
+                if copied < length {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                Ok(())
+            }
+            Self::Reader {
+                mut reader,
+                length: None,
+            } => io::copy(&mut reader, stream).map(|_| ()),
+            Self::Chunks(chunks) => {
+                for chunk in chunks {
+                    stream.write_all(&chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn write_chunked_to(self, stream: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Self::Chunks(chunks) => {
+                for chunk in chunks {
+                    write_chunk(stream, &chunk)?;
+                }
+            }
+            Self::Reader { mut reader, .. } => {
+                let mut buffer = vec![0u8; CHUNK_SIZE];
+                loop {
+                    let read = match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(read) => read,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    write_chunk(stream, &buffer[..read])?;
+                }
+            }
+            Self::Bytes(bytes) => write_chunk(stream, &bytes)?,
+        }
+
+        stream.write_all(b"0\r\n\r\n")
+    }
+}
+
+fn write_chunk(stream: &mut dyn Write, chunk: &[u8]) -> io::Result<()> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+
+    write!(stream, "{:X}\r\n", chunk.len())?;
+    stream.write_all(chunk)?;
+    stream.write_all(b"\r\n")
+}
+
+impl Debug for HttpEntity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bytes(bytes) => f.debug_tuple("Bytes").field(&bytes.len()).finish(),
+            Self::Reader { length, .. } => {
+                f.debug_struct("Reader").field("length", length).finish()
+            }
+            Self::Chunks(_) => f.write_str("Chunks"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_len() {
+        assert_eq!(Some(3), HttpEntity::Bytes(vec![1, 2, 3]).len());
+        assert!(HttpEntity::Bytes(Vec::new()).is_empty());
+        assert_eq!(None, HttpEntity::Chunks(Box::new(std::iter::empty())).len());
+    }
+
+    #[test]
+    fn test_write_reader_with_length() {
+        let entity = HttpEntity::Reader {
+            reader: Box::new("hello world".as_bytes()),
+            length: Some(5),
+        };
+        let mut output = Vec::new();
+        entity.write_to(&mut output).unwrap();
+
+        assert_eq!(b"hello", output.as_slice());
+    }
+
+    #[test]
+    fn test_write_reader_too_short() {
+        let entity = HttpEntity::Reader {
+            reader: Box::new("hi".as_bytes()),
+            length: Some(5),
+        };
+
+        assert!(entity.write_to(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_write_chunked() {
+        let chunks = vec![b"hello".to_vec(), Vec::new(), b", world!!!!!!".to_vec()];
+        let entity = HttpEntity::Chunks(Box::new(chunks.into_iter()));
+        let mut output = Vec::new();
+        entity.write_chunked_to(&mut output).unwrap();
+
+        assert_eq!(
+            "5\r\nhello\r\nD\r\n, world!!!!!!\r\n0\r\n\r\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_chunked_reader() {
+        let entity = HttpEntity::Reader {
+            reader: Box::new("abc".as_bytes()),
+            length: None,
+        };
+        let mut output = Vec::new();
+        entity.write_chunked_to(&mut output).unwrap();
+
+        assert_eq!("3\r\nabc\r\n0\r\n\r\n", String::from_utf8(output).unwrap());
+    }
+}
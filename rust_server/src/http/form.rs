@@ -0,0 +1,487 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+use super::spool::{Spool, Spooled};
+use super::{Headers, HttpStatus, QueryParams};
+
+pub const DEFAULT_SPILL_THRESHOLD: usize = 1024 * 1024;
+
+const MAX_PART_HEADER_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Default)]
+pub struct FormData {
+    pub fields: QueryParams,
+    pub files: Vec<FilePart>,
+}
+
+#[derive(Debug)]
+pub struct FilePart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub headers: Headers,
+    content: Spooled,
+}
+
+#[derive(Debug)]
+pub enum FormError {
+    MissingContentType,
+    UnsupportedContentType(String),
+    MissingBoundary,
+    Malformed(String),
+    TooLarge,
+    Io(io::Error),
+}
+
+impl FormData {
+    pub fn file(&self, name: &str) -> Option<&FilePart> {
+        self.files.iter().find(|file| file.name == name)
+    }
+}
+
+impl FilePart {
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.get_first("Content-Type")
+    }
+
+    pub fn len(&self) -> u64 {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.content.is_in_memory()
+    }
+
+    pub fn reader(&self) -> io::Result<Box<dyn Read + Send + '_>> {
+        self.content.reader()
+    }
+
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        self.content.bytes()
+    }
+
+    pub fn persist(self, path: &Path) -> io::Result<File> {
+        self.content.persist(path)
+    }
+}
+
+impl FormError {
+    pub fn status(&self) -> HttpStatus {
+        match self {
+            Self::MissingContentType | Self::UnsupportedContentType(_) => {
+                HttpStatus::UnsupportedMediaType
+            }
+            Self::TooLarge => HttpStatus::PayloadTooLarge,
+            Self::Io(_) => HttpStatus::InternalServerError,
+            _ => HttpStatus::BadRequest,
+        }
+    }
+}
+
+pub fn parse_form(
+    body: &mut dyn BufRead,
+    content_type: Option<&str>,
+    spill_threshold: usize,
+) -> Result<FormData, FormError> {
+    let content_type = content_type.ok_or(FormError::MissingContentType)?;
+    let (media_type, params) = split_header_params(content_type);
+
+    if media_type.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+        let mut bytes = Vec::new();
+        body.take(spill_threshold as u64 + 1)
+            .read_to_end(&mut bytes)
+            .map_err(FormError::Io)?;
+        if bytes.len() > spill_threshold {
+            return Err(FormError::TooLarge);
+        }
+
+        let body = String::from_utf8(bytes)
+            .map_err(|_| FormError::Malformed(String::from("form body is not UTF-8")))?;
+        return Ok(FormData {
+            fields: QueryParams::parse(&body),
+            files: Vec::new(),
+        });
+    }
+
+    if media_type.eq_ignore_ascii_case("multipart/form-data") {
+        let boundary = params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("boundary"))
+            .map(|(_, value)| value.as_str())
+            .filter(|boundary| !boundary.is_empty())
+            .ok_or(FormError::MissingBoundary)?;
+        return parse_multipart(body, boundary, spill_threshold);
+    }
+
+    Err(FormError::UnsupportedContentType(media_type.to_owned()))
+}
+
+pub fn parse_multipart(
+    body: &mut dyn BufRead,
+    boundary: &str,
+    spill_threshold: usize,
+) -> Result<FormData, FormError> {
+    let delimiter = format!("--{boundary}").into_bytes();
+    let separator = format!("\r\n--{boundary}").into_bytes();
+    let mut form = FormData::default();
+
+    if !copy_until(body, &delimiter, &mut io::sink()).map_err(FormError::Io)? {
+        return Err(FormError::Malformed(String::from(
+            "missing opening boundary",
+        )));
+    }
+
+    loop {
+        let mut next = [0u8; 2];
+        body.read_exact(&mut next)
+            .map_err(|_| FormError::Malformed(String::from("boundary not followed by CRLF")))?;
+        match &next {
+            b"--" => return Ok(form),
+            b"\r\n" => {}
+            _ => {
+                return Err(FormError::Malformed(String::from(
+                    "boundary not followed by CRLF",
+                )))
+            }
+        }
+
+        let mut raw_headers = Vec::new();
+        let found = copy_until(
+            &mut body.take(MAX_PART_HEADER_BYTES as u64),
+            b"\r\n\r\n",
+            &mut raw_headers,
+        )
+        .map_err(FormError::Io)?;
+        if !found {
+            return Err(FormError::Malformed(String::from(
+                "unterminated part headers",
+            )));
+        }
+        let headers = parse_part_headers(&raw_headers)?;
+
+        let mut content = Spool::new(spill_threshold);
+        if !copy_until(body, &separator, &mut content).map_err(FormError::Io)? {
+            return Err(FormError::Malformed(String::from(
+                "missing closing boundary",
+            )));
+        }
+        add_part(&mut form, headers, content.finish().map_err(FormError::Io)?)?;
+    }
+}
+
+// Streams everything before `needle` into `sink` and consumes the needle
+// itself. Returns false if the input ends first.
+fn copy_until(reader: &mut dyn BufRead, needle: &[u8], sink: &mut dyn Write) -> io::Result<bool> {
+    let mut carry: Vec<u8> = Vec::new();
+
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            sink.write_all(&carry)?;
+            return Ok(false);
+        }
+
+        let mut window = std::mem::take(&mut carry);
+        window.extend_from_slice(available);
+
+        if let Some(position) = find(&window, needle) {
+            sink.write_all(&window[..position])?;
+            let consumed = position + needle.len() - (window.len() - available.len());
+            reader.consume(consumed);
+            return Ok(true);
+        }
+
+        let keep = window.len().min(needle.len() - 1);
+        let consumed = available.len();
+        sink.write_all(&window[..window.len() - keep])?;
+        carry = window.split_off(window.len() - keep);
+        reader.consume(consumed);
+    }
+}
+
+fn add_part(form: &mut FormData, headers: Headers, content: Spooled) -> Result<(), FormError> {
+    let disposition = headers
+        .get_first("Content-Disposition")
+        .ok_or_else(|| FormError::Malformed(String::from("part without Content-Disposition")))?;
+    let (kind, params) = split_header_params(disposition);
+    if !kind.eq_ignore_ascii_case("form-data") {
+        return Err(FormError::Malformed(format!(
+            "unexpected disposition {kind:?}"
+        )));
+    }
+
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    };
+    let name =
+        param("name").ok_or_else(|| FormError::Malformed(String::from("part without a name")))?;
+
+    let Some(filename) = param("filename") else {
+        let Spooled::Memory(bytes) = content else {
+            return Err(FormError::TooLarge);
+        };
+        let value = String::from_utf8(bytes)
+            .map_err(|_| FormError::Malformed(format!("field {name:?} is not UTF-8")))?;
+        form.fields.put(&name, &value);
+        return Ok(());
+    };
+
+    form.files.push(FilePart {
+        name,
+        filename: Some(filename).filter(|filename| !filename.is_empty()),
+        headers,
+        content,
+    });
+    Ok(())
+}
+
+fn parse_part_headers(raw: &[u8]) -> Result<Headers, FormError> {
+    let raw = std::str::from_utf8(raw)
+        .map_err(|_| FormError::Malformed(String::from("part headers are not UTF-8")))?;
+    let mut headers = Headers::new();
+
+    for line in raw.split("\r\n") {
+        match line.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => headers.put(key.trim(), value),
+            _ => return Err(FormError::Malformed(format!("bad part header {line:?}"))),
+        }
+    }
+
+    Ok(headers)
+}
+
+pub(crate) fn split_header_params(value: &str) -> (&str, Vec<(String, String)>) {
+    let (main, mut rest) = value.split_once(';').unwrap_or((value, ""));
+    let mut params = Vec::new();
+
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ';']);
+        if rest.is_empty() {
+            break;
+        }
+
+        let key_end = rest.find(['=', ';']).unwrap_or(rest.len());
+        let key = rest[..key_end].trim().to_owned();
+        rest = &rest[key_end..];
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) if value.trim_start().starts_with('"') => {
+                let (value, remaining) = read_quoted(value.trim_start());
+                rest = remaining;
+                value
+            }
+            Some(value) => {
+                let end = value.find(';').unwrap_or(value.len());
+                rest = &value[end..];
+                value[..end].trim().to_owned()
+            }
+            None => String::new(),
+        };
+
+        params.push((key, value));
+    }
+
+    (main.trim(), params)
+}
+
+fn read_quoted(input: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = input.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, &input[i + 1..]),
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    value.push(escaped);
+                }
+            }
+            c => value.push(c),
+        }
+    }
+
+    (value, "")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+impl Display for FormError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingContentType => f.write_str("Form body without a Content-Type"),
+            Self::UnsupportedContentType(media_type) => {
+                write!(f, "Unsupported form content type: {media_type}")
+            }
+            Self::MissingBoundary => f.write_str("Multipart body without a boundary"),
+            Self::Malformed(reason) => write!(f, "Malformed form body: {reason}"),
+            Self::TooLarge => f.write_str("Form field exceeds the size limit"),
+            Self::Io(e) => write!(f, "Error storing upload: {e}"),
+        }
+    }
+}
+
+impl Error for FormError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &str = "preamble\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        Quarterly report\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"upload\"; filename=\"report \\\"q3\\\".csv\"\r\n\
+        Content-Type: text/csv\r\n\
+        \r\n\
+        a,b\r\n1,2\r\n\
+        --XyZ\r\n\
+        Content-Disposition: form-data; name=\"tag\"\r\n\
+        \r\n\
+        finance\r\n\
+        --XyZ--\r\n";
+
+    #[test]
+    fn test_urlencoded() {
+        let form = parse_form(
+            &mut b"name=Ada+Lovelace&lang=en&lang=fr&note=100%25".as_slice(),
+            Some("application/x-www-form-urlencoded; charset=UTF-8"),
+            DEFAULT_SPILL_THRESHOLD,
+        )
+        .unwrap();
+
+        assert_eq!(Some("Ada Lovelace"), form.fields.get_first("name"));
+        assert_eq!(2, form.fields.get("lang").unwrap().len());
+        assert_eq!(Some("100%"), form.fields.get_first("note"));
+        assert!(form.files.is_empty());
+    }
+
+    #[test]
+    fn test_multipart() {
+        let form = parse_form(
+            &mut MULTIPART.as_bytes(),
+            Some("multipart/form-data; boundary=XyZ"),
+            DEFAULT_SPILL_THRESHOLD,
+        )
+        .unwrap();
+
+        assert_eq!(Some("Quarterly report"), form.fields.get_first("title"));
+        assert_eq!(Some("finance"), form.fields.get_first("tag"));
+
+        let upload = form.file("upload").unwrap();
+        assert_eq!(Some("report \"q3\".csv"), upload.filename.as_deref());
+        assert_eq!(Some("text/csv"), upload.content_type());
+        assert!(upload.is_in_memory());
+        assert_eq!(b"a,b\r\n1,2".to_vec(), upload.bytes().unwrap());
+
+        for capacity in 1..8 {
+            let mut reader = std::io::BufReader::with_capacity(capacity, MULTIPART.as_bytes());
+            let form = parse_form(
+                &mut reader,
+                Some("multipart/form-data; boundary=XyZ"),
+                DEFAULT_SPILL_THRESHOLD,
+            )
+            .unwrap();
+
+            assert_eq!(Some("Quarterly report"), form.fields.get_first("title"));
+            let upload = form.file("upload").unwrap();
+            assert_eq!(b"a,b\r\n1,2".to_vec(), upload.bytes().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_large_upload_spills_to_temp_file() {
+        let form = parse_form(
+            &mut MULTIPART.as_bytes(),
+            Some("multipart/form-data; boundary=\"XyZ\""),
+            16,
+        )
+        .unwrap();
+        assert!(form.file("upload").unwrap().is_in_memory());
+
+        let body = MULTIPART
+            .replace("Quarterly report", "Q3")
+            .replace("finance", "fin");
+        let form = parse_form(
+            &mut body.as_bytes(),
+            Some("multipart/form-data; boundary=\"XyZ\""),
+            4,
+        )
+        .unwrap();
+
+        let upload = form.file("upload").unwrap();
+        assert!(!upload.is_in_memory());
+        assert_eq!(8, upload.len());
+        assert_eq!(b"a,b\r\n1,2".to_vec(), upload.bytes().unwrap());
+
+        let error = parse_form(
+            &mut MULTIPART.as_bytes(),
+            Some("multipart/form-data; boundary=XyZ"),
+            4,
+        )
+        .unwrap_err();
+        assert_eq!(HttpStatus::PayloadTooLarge, error.status());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |body: &str, content_type: Option<&str>| {
+            parse_form(&mut body.as_bytes(), content_type, DEFAULT_SPILL_THRESHOLD).unwrap_err()
+        };
+
+        assert_eq!(
+            HttpStatus::UnsupportedMediaType,
+            error("{}", Some("application/json")).status()
+        );
+        assert_eq!(
+            HttpStatus::UnsupportedMediaType,
+            error("a=b", None).status()
+        );
+        assert!(matches!(
+            error("", Some("multipart/form-data")),
+            FormError::MissingBoundary
+        ));
+        assert!(matches!(
+            error(
+                "--b\r\nContent-Disposition: form-data; name=\"x\"\r\n\r\nno end",
+                Some("multipart/form-data; boundary=b")
+            ),
+            FormError::Malformed(_)
+        ));
+    }
+
+    #[test]
+    fn test_split_header_params() {
+        let (main, params) = split_header_params("form-data; name=\"a;b\"; filename=x.txt");
+
+        assert_eq!("form-data", main);
+        assert_eq!(
+            vec![
+                (String::from("name"), String::from("a;b")),
+                (String::from("filename"), String::from("x.txt"))
+            ],
+            params
+        );
+    }
+}
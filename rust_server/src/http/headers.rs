@@ -78,6 +78,18 @@ impl Headers {
         })
     }
 
+    pub fn put_list_item(&mut self, key: &str, item: &str) {
+        let listed = self
+            .get_splitting_commas(key)
+            .into_iter()
+            .flatten()
+            .any(|existing| existing.eq_ignore_ascii_case(item));
+
+        if !listed {
+            self.put(key, item);
+        }
+    }
+
     pub fn remove(&mut self, key: &str) -> Option<Vec<String>> {
         let key = sanitize_key(key);
         self.raw_headers.remove(&key).map(|it| it.values)
@@ -249,4 +261,23 @@ mod tests {
             .into_iter()
             .eq(all_accept));
     }
+
+    #[test]
+    fn test_put_list_item() {
+        let mut headers = Headers::new();
+
+        headers.put("Vary", "Origin, accept-encoding");
+        headers.put_list_item("Vary", "Accept-Encoding");
+        headers.put_list_item("Vary", "Accept-Language");
+
+        assert_eq!(
+            Some(
+                &[
+                    String::from("Origin, accept-encoding"),
+                    String::from("Accept-Language")
+                ][..]
+            ),
+            headers.get("Vary")
+        );
+    }
 }
@@ -0,0 +1,142 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+
+use super::form::split_header_params;
+use super::HttpStatus;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+#[derive(Debug)]
+pub enum JsonError {
+    MissingContentType,
+    UnsupportedContentType(String),
+    Syntax(serde_json::Error),
+    Data(serde_json::Error),
+}
+
+impl JsonError {
+    pub fn status(&self) -> HttpStatus {
+        match self {
+            Self::MissingContentType | Self::UnsupportedContentType(_) => {
+                HttpStatus::UnsupportedMediaType
+            }
+            Self::Syntax(_) => HttpStatus::BadRequest,
+            Self::Data(_) => HttpStatus::UnprocessableEntity,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        match e.classify() {
+            Category::Data => Self::Data(e),
+            Category::Io | Category::Syntax | Category::Eof => Self::Syntax(e),
+        }
+    }
+}
+
+pub fn parse_json<T: DeserializeOwned>(
+    body: impl Read,
+    content_type: Option<&str>,
+) -> Result<T, JsonError> {
+    let content_type = content_type.ok_or(JsonError::MissingContentType)?;
+    let (media_type, _) = split_header_params(content_type);
+
+    if !is_json_media_type(media_type) {
+        return Err(JsonError::UnsupportedContentType(media_type.to_owned()));
+    }
+
+    Ok(serde_json::from_reader(body)?)
+}
+
+fn is_json_media_type(media_type: &str) -> bool {
+    let media_type = media_type.to_ascii_lowercase();
+    media_type == JSON_CONTENT_TYPE
+        || media_type.starts_with("application/") && media_type.ends_with("+json")
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingContentType => f.write_str("JSON body without a Content-Type"),
+            Self::UnsupportedContentType(media_type) => {
+                write!(f, "Expected a JSON content type, got: {media_type}")
+            }
+            Self::Syntax(e) => write!(f, "Malformed JSON body: {e}"),
+            Self::Data(e) => write!(f, "Invalid JSON body: {e}"),
+        }
+    }
+}
+
+impl Error for JsonError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Syntax(e) | Self::Data(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Login {
+        user: String,
+        remember: bool,
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let login: Login = parse_json(
+            br#"{"user": "ada", "remember": true}"#.as_slice(),
+            Some("application/json; charset=utf-8"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Login {
+                user: String::from("ada"),
+                remember: true
+            },
+            login
+        );
+        assert!(parse_json::<Login>(
+            br#"{"user": "ada", "remember": false}"#.as_slice(),
+            Some("application/vnd.api+json")
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |body: &str, content_type: Option<&str>| {
+            parse_json::<Login>(body.as_bytes(), content_type).unwrap_err()
+        };
+
+        assert_eq!(
+            HttpStatus::UnsupportedMediaType,
+            error("{}", Some("text/plain")).status()
+        );
+        assert_eq!(HttpStatus::UnsupportedMediaType, error("{}", None).status());
+        assert_eq!(
+            HttpStatus::BadRequest,
+            error("{\"user\": ", Some("application/json")).status()
+        );
+        assert_eq!(
+            HttpStatus::UnprocessableEntity,
+            error(
+                "{\"user\": 1, \"remember\": true}",
+                Some("application/json")
+            )
+            .status()
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use super::DEFAULT_SPILL_THRESHOLD;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserLimits {
+    pub max_request_line: usize,
+    pub max_headers: usize,
+    pub max_header_bytes: usize,
+    pub max_body_size: usize,
+    pub body_spill_threshold: usize,
+    pub header_timeout: Option<Duration>,
+    pub body_timeout: Option<Duration>,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_request_line: 8 * 1024,
+            max_headers: 100,
+            max_header_bytes: 64 * 1024,
+            max_body_size: 10 * 1024 * 1024,
+            body_spill_threshold: DEFAULT_SPILL_THRESHOLD,
+            header_timeout: Some(Duration::from_secs(10)),
+            body_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl ParserLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_request_line: usize::MAX,
+            max_headers: usize::MAX,
+            max_header_bytes: usize::MAX,
+            max_body_size: usize::MAX,
+            body_spill_threshold: DEFAULT_SPILL_THRESHOLD,
+            header_timeout: None,
+            body_timeout: None,
+        }
+    }
+}
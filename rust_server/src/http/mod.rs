@@ -1,18 +1,52 @@
-use anyhow::{anyhow, Result};
 use std::fmt::{Debug, Display, Formatter};
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
 mod body;
+mod cache_control;
+mod conditional;
+mod cookie;
+mod encoding;
+mod entity;
+mod form;
 mod headers;
+#[cfg(feature = "json")]
+mod json;
+mod limits;
+mod negotiation;
+mod range;
 mod request;
 mod response;
+mod spool;
+mod uri;
 
-pub use body::HttpBody;
+pub use body::{BodyError, HttpBody};
+pub use cache_control::CacheControl;
+pub use conditional::Validators;
+#[cfg(feature = "signed-cookies")]
+pub use cookie::CookieKey;
+pub use cookie::{CookieError, CookieJar, SameSite, SetCookie};
+pub use encoding::{negotiate_encoding, ContentCoding};
+pub use entity::HttpEntity;
+pub use form::{FilePart, FormData, FormError, DEFAULT_SPILL_THRESHOLD};
 pub use headers::Headers;
-pub use request::HttpRequest;
+#[cfg(feature = "json")]
+pub use json::{JsonError, JSON_CONTENT_TYPE};
+pub use limits::ParserLimits;
+pub use negotiation::{
+    negotiate_charset, negotiate_language, negotiate_media_type, parse_accept,
+    parse_accept_charset, parse_accept_language, MediaRange, QualityItem,
+};
+pub use range::{
+    parse_range_header, partial_content, range_not_satisfiable, ByteRange, RangeRequest,
+};
+pub use request::{HttpRequest, ParseError};
 pub use response::HttpResponse;
+pub use uri::{
+    decode_form_component, percent_decode, percent_encode_segment, QueryParams, Uri, UriForm,
+};
 
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum HttpVersion {
     One,
     OnePointOne,
@@ -110,11 +144,14 @@ pub enum HttpStatus {
 }
 
 impl HttpVersion {
-    pub fn build(string: &str) -> Result<Self> {
+    pub fn build(string: &str) -> Result<Self, ParseError> {
         match string {
             "HTTP/1.0" => Ok(Self::One),
             "HTTP/1.1" => Ok(Self::OnePointOne),
-            _ => Err(anyhow!("Unsupported HTTP version")),
+            _ if string.starts_with("HTTP/") => {
+                Err(ParseError::UnsupportedVersion(string.to_owned()))
+            }
+            _ => Err(ParseError::MalformedRequestLine(string.to_owned())),
         }
     }
 }
@@ -140,6 +177,27 @@ impl HttpMethod {
             name: method_name.to_owned(),
         })
     }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::HEAD => "HEAD",
+            Self::CONNECT => "CONNECT",
+            Self::OPTIONS => "OPTIONS",
+            Self::TRACE => "TRACE",
+            Self::PATCH => "PATCH",
+            Self::Custom { name } => name,
+        }
+    }
+}
+
+impl Display for HttpMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl HttpStatus {
@@ -0,0 +1,301 @@
+use super::Headers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRange {
+    pub main_type: String,
+    pub subtype: String,
+    pub params: Vec<(String, String)>,
+    pub quality: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem {
+    pub value: String,
+    pub quality: f32,
+}
+
+impl MediaRange {
+    pub fn parse(item: &str) -> Option<Self> {
+        let mut parts = item.split(';').map(str::trim);
+        let (main_type, subtype) = parts.next()?.split_once('/')?;
+        let (main_type, subtype) = (main_type.trim(), subtype.trim());
+        if main_type.is_empty() || subtype.is_empty() || (main_type == "*" && subtype != "*") {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        let mut quality = 1.0;
+        for part in parts {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+
+            if key.eq_ignore_ascii_case("q") {
+                quality = parse_quality(value)?;
+                break;
+            }
+            params.push((key.to_ascii_lowercase(), value.to_owned()));
+        }
+
+        Some(Self {
+            main_type: main_type.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params,
+            quality,
+        })
+    }
+
+    pub fn matches(&self, media_type: &MediaRange) -> bool {
+        (self.main_type == "*" || self.main_type == media_type.main_type)
+            && (self.subtype == "*" || self.subtype == media_type.subtype)
+            && self.params.iter().all(|(key, value)| {
+                media_type
+                    .params
+                    .iter()
+                    .any(|(k, v)| k == key && v.eq_ignore_ascii_case(value))
+            })
+    }
+
+    fn specificity(&self) -> usize {
+        match (self.main_type.as_str(), self.subtype.as_str()) {
+            ("*", _) => 0,
+            (_, "*") => 1,
+            _ => 2 + self.params.len(),
+        }
+    }
+}
+
+pub fn parse_accept(headers: &Headers) -> Vec<MediaRange> {
+    headers
+        .get_splitting_commas("Accept")
+        .into_iter()
+        .flatten()
+        .filter_map(MediaRange::parse)
+        .collect()
+}
+
+pub fn parse_accept_language(headers: &Headers) -> Vec<QualityItem> {
+    parse_quality_items(headers, "Accept-Language")
+}
+
+pub fn parse_accept_charset(headers: &Headers) -> Vec<QualityItem> {
+    parse_quality_items(headers, "Accept-Charset")
+}
+
+pub fn negotiate_media_type<'a>(headers: &Headers, offered: &[&'a str]) -> Option<&'a str> {
+    // An Accept header with nothing parseable in it says nothing about the
+    // client's preferences, so it's treated the same as a missing one.
+    let ranges = parse_accept(headers);
+    if ranges.is_empty() {
+        return offered.first().copied();
+    }
+
+    best_offer(offered, |offer| {
+        let offer = MediaRange::parse(offer)?;
+        ranges
+            .iter()
+            .filter(|range| range.matches(&offer))
+            .max_by_key(|range| range.specificity())
+            .map(|range| range.quality)
+    })
+}
+
+pub fn negotiate_language<'a>(headers: &Headers, offered: &[&'a str]) -> Option<&'a str> {
+    negotiate_items(headers, "Accept-Language", offered, |range, tag| {
+        if range == "*" {
+            return Some(0);
+        }
+        let matches = tag.eq_ignore_ascii_case(range)
+            || tag
+                .get(..range.len() + 1)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{range}-")));
+        matches.then_some(range.len())
+    })
+}
+
+pub fn negotiate_charset<'a>(headers: &Headers, offered: &[&'a str]) -> Option<&'a str> {
+    negotiate_items(
+        headers,
+        "Accept-Charset",
+        offered,
+        |range, charset| match range {
+            "*" => Some(0),
+            _ if range.eq_ignore_ascii_case(charset) => Some(1),
+            _ => None,
+        },
+    )
+}
+
+fn negotiate_items<'a>(
+    headers: &Headers,
+    key: &str,
+    offered: &[&'a str],
+    specificity: impl Fn(&str, &str) -> Option<usize>,
+) -> Option<&'a str> {
+    if headers.get(key).is_none() {
+        return offered.first().copied();
+    }
+    let items = parse_quality_items(headers, key);
+
+    best_offer(offered, |offer| {
+        items
+            .iter()
+            .filter_map(|item| Some((specificity(&item.value, offer)?, item.quality)))
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, quality)| quality)
+    })
+}
+
+fn best_offer<'a>(offered: &[&'a str], quality: impl Fn(&str) -> Option<f32>) -> Option<&'a str> {
+    let mut best: Option<(&str, f32)> = None;
+
+    for offer in offered {
+        let quality = quality(offer).unwrap_or(0.0);
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((offer, quality));
+        }
+    }
+
+    best.map(|(offer, _)| offer)
+}
+
+fn parse_quality_items(headers: &Headers, key: &str) -> Vec<QualityItem> {
+    parse_quality_list(headers.get_splitting_commas(key).into_iter().flatten())
+        .into_iter()
+        .map(|(value, quality)| QualityItem {
+            value: value.to_owned(),
+            quality,
+        })
+        .collect()
+}
+
+pub(crate) fn parse_quality_list<'a>(items: impl Iterator<Item = &'a str>) -> Vec<(&'a str, f32)> {
+    items
+        .filter(|item| !item.is_empty())
+        .filter_map(|item| {
+            let mut params = item.split(';').map(str::trim);
+            let value = params.next()?;
+            let mut quality = 1.0;
+
+            for param in params {
+                if let Some((key, q)) = param.split_once('=') {
+                    if key.trim().eq_ignore_ascii_case("q") {
+                        quality = parse_quality(q.trim())?;
+                    }
+                }
+            }
+
+            Some((value, quality))
+        })
+        .collect()
+}
+
+fn parse_quality(value: &str) -> Option<f32> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|quality| (0.0..=1.0).contains(quality))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(key: &str, value: &str) -> Headers {
+        let mut headers = Headers::new();
+        headers.put(key, value);
+        headers
+    }
+
+    #[test]
+    fn test_parse_accept() {
+        let ranges = parse_accept(&headers(
+            "Accept",
+            "text/html;level=1;q=0.7;ext=x, application/*;q=0.2, */*, bogus, */html",
+        ));
+
+        assert_eq!(
+            vec![
+                MediaRange {
+                    main_type: String::from("text"),
+                    subtype: String::from("html"),
+                    params: vec![(String::from("level"), String::from("1"))],
+                    quality: 0.7
+                },
+                MediaRange {
+                    main_type: String::from("application"),
+                    subtype: String::from("*"),
+                    params: Vec::new(),
+                    quality: 0.2
+                },
+                MediaRange {
+                    main_type: String::from("*"),
+                    subtype: String::from("*"),
+                    params: Vec::new(),
+                    quality: 1.0
+                },
+            ],
+            ranges
+        );
+    }
+
+    #[test]
+    fn test_negotiate_media_type() {
+        let offered = ["text/html; charset=utf-8", "application/json", "text/plain"];
+        let negotiate = |accept: &str| negotiate_media_type(&headers("Accept", accept), &offered);
+
+        assert_eq!(Some("application/json"), negotiate("application/json"));
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            negotiate("text/*;q=0.9, application/json;q=0.5")
+        );
+        assert_eq!(Some("text/plain"), negotiate("text/*, text/html;q=0"));
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            negotiate("text/html;charset=UTF-8")
+        );
+        assert_eq!(Some("text/html; charset=utf-8"), negotiate("*/*"));
+        assert_eq!(None, negotiate("image/png"));
+        assert_eq!(Some("text/html; charset=utf-8"), negotiate("garbage"));
+        assert_eq!(
+            Some("text/html; charset=utf-8"),
+            negotiate_media_type(&Headers::new(), &offered)
+        );
+    }
+
+    #[test]
+    fn test_negotiate_language() {
+        let offered = ["en-US", "pt-BR", "fr"];
+        let negotiate =
+            |accept: &str| negotiate_language(&headers("Accept-Language", accept), &offered);
+
+        assert_eq!(Some("pt-BR"), negotiate("pt, en;q=0.8"));
+        assert_eq!(Some("fr"), negotiate("de, fr-CA, FR;q=0.5"));
+        assert_eq!(Some("en-US"), negotiate("*;q=0.1, pt;q=0"));
+        assert_eq!(None, negotiate("de, e"));
+    }
+
+    #[test]
+    fn test_negotiate_charset() {
+        let offered = ["utf-8", "iso-8859-1"];
+        let negotiate =
+            |accept: &str| negotiate_charset(&headers("Accept-Charset", accept), &offered);
+
+        assert_eq!(Some("iso-8859-1"), negotiate("ISO-8859-1, utf-8;q=0.7"));
+        assert_eq!(Some("utf-8"), negotiate("*"));
+        assert_eq!(None, negotiate("utf-16"));
+    }
+
+    #[test]
+    fn test_parse_quality_list() {
+        let items = "gzip;q=0.8, br, deflate;q=2, compress;Q=0 ,"
+            .split(',')
+            .map(str::trim);
+
+        assert_eq!(
+            vec![("gzip", 0.8), ("br", 1.0), ("compress", 0.0)],
+            parse_quality_list(items)
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{HttpResponse, HttpStatus};
+
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeRequest {
+    Ignored,
+    Satisfiable(Vec<ByteRange>),
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    pub fn content_range(&self, total_length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total_length)
+    }
+}
+
+pub fn parse_range_header(header: &str, total_length: u64) -> RangeRequest {
+    let Some(specs) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Ignored;
+    };
+
+    let mut ranges = Vec::new();
+    let mut spec_count = 0;
+
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        spec_count += 1;
+        if spec_count > MAX_RANGES {
+            return RangeRequest::Ignored;
+        }
+
+        let Some((first, last)) = spec.split_once('-') else {
+            return RangeRequest::Ignored;
+        };
+
+        let range = match (parse_position(first), parse_position(last)) {
+            (Some(Some(start)), Some(end)) => {
+                if end.is_some_and(|end| end < start) {
+                    return RangeRequest::Ignored;
+                }
+                if start >= total_length {
+                    continue;
+                }
+                let end = end.map_or(total_length - 1, |end| end.min(total_length - 1));
+                ByteRange { start, end }
+            }
+            (Some(None), Some(Some(suffix))) => {
+                if suffix == 0 || total_length == 0 {
+                    continue;
+                }
+                ByteRange {
+                    start: total_length.saturating_sub(suffix),
+                    end: total_length - 1,
+                }
+            }
+            _ => return RangeRequest::Ignored,
+        };
+
+        ranges.push(range);
+    }
+
+    if spec_count == 0 {
+        return RangeRequest::Ignored;
+    }
+    if ranges.is_empty() {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    // Overlapping ranges would otherwise send the same bytes many times over.
+    RangeRequest::Satisfiable(coalesce(ranges))
+}
+
+fn coalesce(mut ranges: Vec<ByteRange>) -> Vec<ByteRange> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + 1 => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+fn parse_position(position: &str) -> Option<Option<u64>> {
+    let position = position.trim();
+    if position.is_empty() {
+        return Some(None);
+    }
+    if !position.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    position.parse().ok().map(Some)
+}
+
+pub fn partial_content<R>(
+    source: R,
+    ranges: &[ByteRange],
+    total_length: u64,
+    content_type: &str,
+) -> HttpResponse
+where
+    R: Read + Seek + Send + 'static,
+{
+    let mut response = HttpResponse::new(HttpStatus::PartialContent);
+
+    if let [range] = ranges {
+        response
+            .headers
+            .put("Content-Range", &range.content_range(total_length));
+        let reader = RangesReader::new(source, vec![Segment::from(*range)]);
+        response.reader_entity(reader, Some(range.length()), content_type);
+        return response;
+    }
+
+    let boundary = generate_boundary();
+    let mut segments = Vec::with_capacity(ranges.len() * 2 + 1);
+    for range in ranges {
+        let part_headers = format!(
+            "\r\n--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: {}\r\n\r\n",
+            range.content_range(total_length)
+        );
+        segments.push(Segment::Bytes(Cursor::new(part_headers.into_bytes())));
+        segments.push(Segment::from(*range));
+    }
+    segments.push(Segment::Bytes(Cursor::new(
+        format!("\r\n--{boundary}--\r\n").into_bytes(),
+    )));
+
+    let length = segments.iter().map(Segment::len).sum();
+    let reader = RangesReader::new(source, segments);
+    response.reader_entity(
+        reader,
+        Some(length),
+        &format!("multipart/byteranges; boundary={boundary}"),
+    );
+    response
+}
+
+pub fn range_not_satisfiable(total_length: u64) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpStatus::RangeNotSatisfiable);
+    response
+        .headers
+        .put("Content-Range", &format!("bytes */{total_length}"));
+    response
+}
+
+fn generate_boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{nanos:016x}{count:08x}")
+}
+
+enum Segment {
+    Bytes(Cursor<Vec<u8>>),
+    Source { position: u64, remaining: u64 },
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(cursor) => cursor.get_ref().len() as u64,
+            Self::Source { remaining, .. } => *remaining,
+        }
+    }
+}
+
+impl From<ByteRange> for Segment {
+    fn from(range: ByteRange) -> Self {
+        Self::Source {
+            position: range.start,
+            remaining: range.length(),
+        }
+    }
+}
+
+struct RangesReader<R> {
+    source: R,
+    segments: VecDeque<Segment>,
+}
+
+impl<R: Read + Seek> RangesReader<R> {
+    fn new(source: R, segments: Vec<Segment>) -> Self {
+        Self {
+            source,
+            segments: segments.into(),
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for RangesReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(segment) = self.segments.front_mut() {
+            let read = match segment {
+                Segment::Bytes(cursor) => cursor.read(buf)?,
+                Segment::Source {
+                    position,
+                    remaining,
+                } => {
+                    if *remaining == 0 {
+                        0
+                    } else {
+                        let limit = buf.len().min(*remaining as usize);
+                        self.source.seek(SeekFrom::Start(*position))?;
+                        let read = self.source.read(&mut buf[..limit])?;
+                        if read == 0 {
+                            return Err(io::ErrorKind::UnexpectedEof.into());
+                        }
+                        *position += read as u64;
+                        *remaining -= read as u64;
+                        read
+                    }
+                }
+            };
+
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            self.segments.pop_front();
+        }
+
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpEntity;
+
+    fn range(start: u64, end: u64) -> ByteRange {
+        ByteRange { start, end }
+    }
+
+    fn read_entity(response: HttpResponse) -> String {
+        let mut output = String::new();
+        if let Some(HttpEntity::Reader { mut reader, .. }) = response.entity {
+            reader.read_to_string(&mut output).unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn test_parse_single() {
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 9)]),
+            parse_range_header("bytes=0-9", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(90, 99)]),
+            parse_range_header("bytes=90-", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(50, 99)]),
+            parse_range_header("bytes=50-1000", 100)
+        );
+    }
+
+    #[test]
+    fn test_parse_suffix() {
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(80, 99)]),
+            parse_range_header("bytes=-20", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 99)]),
+            parse_range_header("bytes=-500", 100)
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple() {
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 0), range(10, 19), range(99, 99)]),
+            parse_range_header("bytes=0-0, 10-19, 200-300, -1", 100)
+        );
+    }
+
+    #[test]
+    fn test_parse_merges_ranges() {
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 14), range(20, 29)]),
+            parse_range_header("bytes=20-29, 5-14, 0-4, 10-12", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(90, 99)]),
+            parse_range_header("bytes=-5, 90-94", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 99)]),
+            parse_range_header("bytes=0-59, 40-99", 100)
+        );
+        assert_eq!(
+            RangeRequest::Satisfiable(vec![range(0, 9)]),
+            parse_range_header(&format!("bytes={}", ["0-9"; 11].join(",")), 100)
+        );
+    }
+
+    #[test]
+    fn test_parse_unsatisfiable() {
+        assert_eq!(
+            RangeRequest::Unsatisfiable,
+            parse_range_header("bytes=100-", 100)
+        );
+        assert_eq!(
+            RangeRequest::Unsatisfiable,
+            parse_range_header("bytes=-0", 100)
+        );
+        assert_eq!(
+            RangeRequest::Unsatisfiable,
+            parse_range_header("bytes=0-", 0)
+        );
+    }
+
+    #[test]
+    fn test_parse_ignored() {
+        for header in [
+            "items=0-1",
+            "bytes=",
+            "bytes=5-1",
+            "bytes=a-b",
+            "bytes=1",
+            "bytes=-",
+        ] {
+            assert_eq!(RangeRequest::Ignored, parse_range_header(header, 100));
+        }
+
+        let many = format!("bytes={}", vec!["0-1"; MAX_RANGES + 1].join(","));
+        assert_eq!(RangeRequest::Ignored, parse_range_header(&many, 100));
+    }
+
+    #[test]
+    fn test_partial_content_single() {
+        let source = Cursor::new(b"0123456789".to_vec());
+        let response = partial_content(source, &[range(2, 5)], 10, "text/plain");
+
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        assert_eq!(
+            Some("bytes 2-5/10"),
+            response.headers.get_first("Content-Range")
+        );
+        assert_eq!(Some("4"), response.headers.get_first("Content-Length"));
+        assert_eq!("2345", read_entity(response));
+    }
+
+    #[test]
+    fn test_partial_content_multiple() {
+        let source = Cursor::new(b"0123456789".to_vec());
+        let response = partial_content(source, &[range(0, 1), range(8, 9)], 10, "text/plain");
+
+        let content_type = response.headers.get_first("Content-Type").unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+        let length = response.headers.get_first("Content-Length").unwrap();
+        let length = length.parse::<usize>().unwrap();
+
+        let body = read_entity(response);
+        assert_eq!(length, body.len());
+        assert_eq!(
+            format!(
+                "\r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\
+                 \r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\
+                 \r\n--{boundary}--\r\n"
+            ),
+            body
+        );
+    }
+
+    #[test]
+    fn test_range_not_satisfiable() {
+        let response = range_not_satisfiable(10);
+
+        assert_eq!(HttpStatus::RangeNotSatisfiable, response.status);
+        assert_eq!(
+            Some("bytes */10"),
+            response.headers.get_first("Content-Range")
+        );
+    }
+}
@@ -1,23 +1,65 @@
-use crate::http::{Headers, HttpMethod, HttpVersion};
-use anyhow::{anyhow, Result};
-use std::io::BufRead;
+use crate::http::{
+    negotiate_charset, negotiate_language, negotiate_media_type, CookieJar, FormData, FormError,
+    Headers, HttpMethod, HttpStatus, HttpVersion, ParserLimits, Uri, UriForm,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead, Read};
 
-use super::body::HttpBody;
+use super::body::{BodyError, HttpBody};
+use super::form;
 
 #[derive(Debug)]
 pub struct HttpRequest {
     pub method: HttpMethod,
-    pub path: String,
+    pub uri: Uri,
     pub version: HttpVersion,
     pub headers: Headers,
     pub body: Option<HttpBody>,
+    pub path_params: HashMap<String, String>,
 
     pub raw_request: String,
 }
 
+#[derive(Debug)]
+pub enum ParseError {
+    MalformedRequestLine(String),
+    MalformedHeader(String),
+    InvalidUri(String),
+    UnsupportedVersion(String),
+    RequestLineTooLong,
+    HeadersTooLarge,
+    PayloadTooLarge,
+    NonUtf8,
+    TruncatedBody,
+    Body(BodyError),
+    Io(io::Error),
+}
+
 impl HttpRequest {
-    pub fn build(mut buf_reader: &mut dyn BufRead) -> Result<Self> {
-        let request_line = read_utf8_line(&mut buf_reader)?;
+    pub fn build(buf_reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Self::build_with_limits(buf_reader, &ParserLimits::default())
+    }
+
+    pub fn build_with_limits(
+        buf_reader: &mut dyn BufRead,
+        limits: &ParserLimits,
+    ) -> Result<Self, ParseError> {
+        let mut request = Self::read_head(buf_reader, limits)?;
+        request.read_body(buf_reader, limits)?;
+        Ok(request)
+    }
+
+    pub fn read_head(
+        buf_reader: &mut dyn BufRead,
+        limits: &ParserLimits,
+    ) -> Result<Self, ParseError> {
+        let request_line = read_limited_line(buf_reader, limits.max_request_line)?
+            .ok_or(ParseError::RequestLineTooLong)?;
+        if !request_line.ends_with('\n') {
+            return Err(ParseError::MalformedRequestLine(request_line));
+        }
         let mut raw_request = request_line.clone();
 
         let props = request_line
@@ -26,13 +68,31 @@ impl HttpRequest {
             .collect::<Vec<_>>();
 
         if props.len() != 3 {
-            return Err(anyhow!("Malformed first request line"));
+            return Err(ParseError::MalformedRequestLine(
+                request_line.trim().to_owned(),
+            ));
+        }
+        let version = HttpVersion::build(props[2])?;
+        let method = HttpMethod::new(props[0]);
+        let uri = Uri::parse(props[1])?;
+        let form_allowed = match uri.form {
+            UriForm::Origin | UriForm::Absolute => true,
+            UriForm::Asterisk => method == HttpMethod::OPTIONS,
+            UriForm::Authority => method == HttpMethod::CONNECT,
+        };
+        if !form_allowed {
+            return Err(ParseError::InvalidUri(props[1].to_owned()));
         }
 
         let mut headers = Headers::new();
+        let mut header_count = 0;
+        let mut header_bytes = 0;
 
         loop {
-            let header_line = read_utf8_line(&mut buf_reader)?;
+            let remaining = limits.max_header_bytes.saturating_sub(header_bytes);
+            let header_line =
+                read_limited_line(buf_reader, remaining)?.ok_or(ParseError::HeadersTooLarge)?;
+            header_bytes += header_line.len();
             raw_request.push_str(&header_line);
             let header_line = header_line.trim();
 
@@ -40,35 +100,342 @@ impl HttpRequest {
                 break;
             }
 
-            if let Some((key, value)) = header_line.split_once(':') {
-                headers.put(key, value);
+            header_count += 1;
+            if header_count > limits.max_headers {
+                return Err(ParseError::HeadersTooLarge);
             }
-        }
-
-        let body = HttpBody::build(&headers, buf_reader);
 
-        if let Some(body) = &body {
-            raw_request.push_str(&body.as_str_lossy());
+            match header_line.split_once(':') {
+                Some((key, value)) if is_token(key) => headers.put(key, value),
+                _ => return Err(ParseError::MalformedHeader(header_line.to_owned())),
+            }
         }
 
-        let method = HttpMethod::new(props[0]);
-        let path = props[1].to_owned();
-        let version = HttpVersion::build(props[2])?;
-
         Ok(Self {
             method,
-            path,
+            uri,
             version,
             headers,
-            body,
+            body: None,
+            path_params: HashMap::new(),
             raw_request,
         })
     }
+
+    pub fn read_body(
+        &mut self,
+        buf_reader: &mut dyn BufRead,
+        limits: &ParserLimits,
+    ) -> Result<(), ParseError> {
+        self.body = HttpBody::build_limited(
+            &mut self.headers,
+            buf_reader,
+            limits.max_body_size,
+            limits.body_spill_threshold,
+        )?;
+
+        // Spilled bodies stay on disk rather than being copied back into memory here.
+        if let Some(bytes) = self.body.as_ref().and_then(HttpBody::as_bytes) {
+            self.raw_request.push_str(&String::from_utf8_lossy(bytes));
+        }
+        Ok(())
+    }
+
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.path_params.get(name).map(String::as_str)
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.uri.query.get_first(name)
+    }
+
+    pub fn form(&self) -> Result<FormData, FormError> {
+        match &self.body {
+            Some(body) => body.form(),
+            None => form::parse_form(&mut io::empty(), self.headers.get_first("Content-Type"), 0),
+        }
+    }
+
+    pub fn cookies(&self) -> CookieJar {
+        CookieJar::parse(&self.headers)
+    }
+
+    pub fn preferred_media_type<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        negotiate_media_type(&self.headers, offered)
+    }
+
+    pub fn preferred_language<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        negotiate_language(&self.headers, offered)
+    }
+
+    pub fn preferred_charset<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        negotiate_charset(&self.headers, offered)
+    }
+
+    pub fn keep_alive(&self) -> bool {
+        let connection = self
+            .headers
+            .get_splitting_commas("Connection")
+            .into_iter()
+            .flatten();
+
+        let default = self.version == HttpVersion::OnePointOne;
+        connection.fold(default, |keep_alive, option| {
+            if option.eq_ignore_ascii_case("close") {
+                false
+            } else if option.eq_ignore_ascii_case("keep-alive") {
+                true
+            } else {
+                keep_alive
+            }
+        })
+    }
+}
+
+impl ParseError {
+    pub fn status(&self) -> HttpStatus {
+        match self {
+            Self::UnsupportedVersion(_) => HttpStatus::HTTPVersionNotSupported,
+            Self::RequestLineTooLong => HttpStatus::URITooLong,
+            Self::HeadersTooLarge => HttpStatus::RequestHeaderFieldsTooLarge,
+            Self::PayloadTooLarge => HttpStatus::PayloadTooLarge,
+            Self::Body(BodyError::UnsupportedTransferEncoding(_)) => HttpStatus::NotImplemented,
+            Self::Io(e) if is_timeout(e) => HttpStatus::RequestTimeout,
+            _ => HttpStatus::BadRequest,
+        }
+    }
 }
 
-fn read_utf8_line(buf_reader: &mut dyn BufRead) -> Result<String> {
-    let mut request_line = Vec::new();
-    buf_reader.read_until(b'\n', &mut request_line)?;
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MalformedRequestLine(line) => write!(f, "Malformed request line: {line:?}"),
+            Self::MalformedHeader(line) => write!(f, "Malformed header: {line:?}"),
+            Self::InvalidUri(target) => write!(f, "Invalid request target: {target:?}"),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported HTTP version: {version}"),
+            Self::RequestLineTooLong => f.write_str("Request line too long"),
+            Self::HeadersTooLarge => f.write_str("Request headers too large"),
+            Self::PayloadTooLarge => f.write_str("Request body too large"),
+            Self::NonUtf8 => f.write_str("Unexpected non UTF-8 string"),
+            Self::TruncatedBody => f.write_str("Request body ended early"),
+            Self::Body(e) => write!(f, "{e}"),
+            Self::Io(e) => write!(f, "Error reading request: {e}"),
+        }
+    }
+}
 
-    String::from_utf8(request_line).map_err(|_| anyhow!("Unexpected non UTF-8 string"))
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Body(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<BodyError> for ParseError {
+    fn from(e: BodyError) -> Self {
+        match e {
+            BodyError::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => Self::TruncatedBody,
+            BodyError::TooLarge => Self::PayloadTooLarge,
+            BodyError::Io(e) => Self::Io(e),
+            e => Self::Body(e),
+        }
+    }
+}
+
+fn read_limited_line(
+    buf_reader: &mut dyn BufRead,
+    limit: usize,
+) -> Result<Option<String>, ParseError> {
+    let mut line = Vec::new();
+    let read_limit = (limit as u64).saturating_add(1);
+    buf_reader.take(read_limit).read_until(b'\n', &mut line)?;
+
+    if line.len() > limit {
+        return Ok(None);
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| ParseError::NonUtf8)
+}
+
+pub(crate) fn is_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &str) -> HttpRequest {
+        HttpRequest::build(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_build() {
+        let request =
+            request("POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello");
+
+        assert_eq!(HttpMethod::POST, request.method);
+        assert_eq!("/echo", request.uri.target);
+        assert_eq!("/echo", request.uri.path);
+        assert!(request.version == HttpVersion::OnePointOne);
+        assert_eq!(Some("localhost"), request.headers.get_first("host"));
+        assert_eq!("hello", request.body.unwrap().as_str_lossy());
+    }
+
+    #[test]
+    fn test_uri() {
+        let request = request("GET /search%20results/?q=rust+http&page=2 HTTP/1.1\r\n\r\n");
+
+        assert_eq!("/search%20results/?q=rust+http&page=2", request.uri.target);
+        assert_eq!("/search results/", request.uri.path);
+        assert_eq!(Some("rust http"), request.query_param("q"));
+        assert_eq!(Some("2"), request.query_param("page"));
+
+        assert!(HttpRequest::build(&mut "OPTIONS * HTTP/1.1\r\n\r\n".as_bytes()).is_ok());
+        assert!(matches!(
+            parse_error(b"GET * HTTP/1.1\r\n\r\n"),
+            ParseError::InvalidUri(_)
+        ));
+    }
+
+    #[test]
+    fn test_form() {
+        let request = request(
+            "POST /login HTTP/1.1\r\n\
+            Content-Type: application/x-www-form-urlencoded\r\n\
+            Content-Length: 22\r\n\r\n\
+            user=ada&pass=s3cr%3Dt",
+        );
+
+        let form = request.form().unwrap();
+        assert_eq!(Some("ada"), form.fields.get_first("user"));
+        assert_eq!(Some("s3cr=t"), form.fields.get_first("pass"));
+    }
+
+    #[test]
+    fn test_keep_alive_defaults() {
+        assert!(request("GET / HTTP/1.1\r\n\r\n").keep_alive());
+        assert!(!request("GET / HTTP/1.0\r\n\r\n").keep_alive());
+    }
+
+    #[test]
+    fn test_keep_alive_connection_header() {
+        assert!(!request("GET / HTTP/1.1\r\nConnection: close\r\n\r\n").keep_alive());
+        assert!(request("GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n").keep_alive());
+        assert!(!request("GET / HTTP/1.1\r\nConnection: upgrade, close\r\n\r\n").keep_alive());
+    }
+
+    fn parse_error(raw: &[u8]) -> ParseError {
+        HttpRequest::build(&mut &raw[..]).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases: [(&[u8], HttpStatus); 7] = [
+            (b"GET /\r\n\r\n", HttpStatus::BadRequest),
+            (
+                b"GET / HTTP/2.0\r\n\r\n",
+                HttpStatus::HTTPVersionNotSupported,
+            ),
+            (b"GET / FTP\r\n\r\n", HttpStatus::BadRequest),
+            (
+                b"GET / HTTP/1.1\r\nno colon\r\n\r\n",
+                HttpStatus::BadRequest,
+            ),
+            (
+                b"GET / HTTP/1.1\r\nBad Name: x\r\n\r\n",
+                HttpStatus::BadRequest,
+            ),
+            (b"GET /\xff HTTP/1.1\r\n\r\n", HttpStatus::BadRequest),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n",
+                HttpStatus::NotImplemented,
+            ),
+        ];
+
+        for (raw, status) in cases {
+            assert_eq!(status, parse_error(raw).status());
+        }
+    }
+
+    #[test]
+    fn test_truncated_body() {
+        let error = parse_error(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello");
+
+        assert!(matches!(error, ParseError::TruncatedBody));
+        assert_eq!(HttpStatus::BadRequest, error.status());
+    }
+
+    #[test]
+    fn test_non_utf8() {
+        assert!(matches!(
+            parse_error(b"GET / HTTP/1.1\r\nX: \xc3\x28\r\n\r\n"),
+            ParseError::NonUtf8
+        ));
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = ParserLimits {
+            max_request_line: 32,
+            max_headers: 2,
+            max_header_bytes: 64,
+            max_body_size: 4,
+            ..ParserLimits::default()
+        };
+        let status = |raw: &str| {
+            HttpRequest::build_with_limits(&mut raw.as_bytes(), &limits)
+                .map(|_| HttpStatus::Ok)
+                .unwrap_or_else(|e| e.status())
+        };
+
+        assert_eq!(
+            HttpStatus::Ok,
+            status("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n\r\n")
+        );
+        assert_eq!(
+            HttpStatus::URITooLong,
+            status(&format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(32)))
+        );
+        assert_eq!(
+            HttpStatus::RequestHeaderFieldsTooLarge,
+            status("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n")
+        );
+        assert_eq!(
+            HttpStatus::RequestHeaderFieldsTooLarge,
+            status(&format!("GET / HTTP/1.1\r\nA: {}\r\n\r\n", "x".repeat(64)))
+        );
+        assert_eq!(
+            HttpStatus::PayloadTooLarge,
+            status("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello")
+        );
+        assert_eq!(
+            HttpStatus::PayloadTooLarge,
+            status("POST / HTTP/1.1\r\nContent-Length: 99999999999999\r\n\r\n")
+        );
+        assert_eq!(
+            HttpStatus::PayloadTooLarge,
+            status("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n")
+        );
+    }
 }
@@ -1,15 +1,17 @@
 use crate::http::{Headers, HttpVersion};
-use std::io::Write;
-use std::net::TcpStream;
+use std::io::{self, BufWriter, Read, Write};
 
-use super::HttpStatus;
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+use super::{CacheControl, HttpEntity, HttpStatus, SetCookie};
 
 #[derive(Debug)]
 pub struct HttpResponse {
     pub version: HttpVersion,
     pub status: HttpStatus,
     pub headers: Headers,
-    pub entity: Option<Vec<u8>>,
+    pub entity: Option<HttpEntity>,
 }
 
 impl HttpResponse {
@@ -23,32 +25,257 @@ impl HttpResponse {
     }
 
     pub fn entity(&mut self, entity: &[u8], content_type: &str) {
-        self.headers
-            .put("Content-Length", &entity.len().to_string());
-        self.headers.put("Content-Type", content_type);
-
-        self.entity = Some(Vec::from(entity));
+        self.set_entity(HttpEntity::Bytes(Vec::from(entity)), content_type);
     }
 
     pub fn str_entity(&mut self, entity: &str, content_type: &str) {
         self.entity(entity.as_bytes(), content_type);
     }
 
-    pub fn write(&self, mut stream: TcpStream) {
-        let response = format!(
+    #[cfg(feature = "json")]
+    pub fn json<T: Serialize + ?Sized>(&mut self, value: &T) -> serde_json::Result<()> {
+        let entity = serde_json::to_vec(value)?;
+        self.entity(&entity, super::JSON_CONTENT_TYPE);
+        Ok(())
+    }
+
+    pub fn reader_entity<R>(&mut self, reader: R, length: Option<u64>, content_type: &str)
+    where
+        R: Read + Send + 'static,
+    {
+        let reader = Box::new(reader);
+        self.set_entity(HttpEntity::Reader { reader, length }, content_type);
+    }
+
+    pub fn chunked_entity<I>(&mut self, chunks: I, content_type: &str)
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        let chunks = Box::new(chunks.into_iter());
+        self.set_entity(HttpEntity::Chunks(chunks), content_type);
+    }
+
+    pub fn set_entity(&mut self, entity: HttpEntity, content_type: &str) {
+        match entity.len() {
+            Some(length) => self
+                .headers
+                .set_all("Content-Length", &[&length.to_string()]),
+            None => self.headers.remove("Content-Length"),
+        };
+        self.headers.set_all("Content-Type", &[content_type]);
+
+        self.entity = Some(entity);
+    }
+
+    pub fn cache_control(&mut self, policy: &CacheControl) {
+        self.headers
+            .set_all("Cache-Control", &[&policy.to_string()]);
+    }
+
+    pub fn set_cookie(&mut self, cookie: &SetCookie) {
+        self.headers.put("Set-Cookie", &cookie.to_string());
+    }
+
+    pub fn write(&mut self, stream: &mut dyn Write) -> io::Result<u64> {
+        self.write_message(stream, true)
+    }
+
+    // Sends the same headers `write` would, but no body, as a HEAD response requires.
+    pub fn write_head(&mut self, stream: &mut dyn Write) -> io::Result<u64> {
+        self.write_message(stream, false)
+    }
+
+    fn write_message(&mut self, stream: &mut dyn Write, send_body: bool) -> io::Result<u64> {
+        let entity = self.entity.take();
+        let unknown_length = entity.as_ref().is_some_and(|entity| entity.len().is_none());
+        let chunked = unknown_length && self.version == HttpVersion::OnePointOne;
+
+        if chunked {
+            self.headers.remove("Content-Length");
+            self.headers.set_all("Transfer-Encoding", &["chunked"]);
+        } else if unknown_length {
+            self.headers.set_all("Connection", &["close"]);
+        } else if entity.is_none()
+            && self.may_have_body()
+            && self.headers.get("Content-Length").is_none()
+        {
+            self.headers.put("Content-Length", "0");
+        }
+
+        let mut writer = BufWriter::new(stream);
+        write!(
+            writer,
             "{} {} {}\r\n{}\r\n",
             self.version,
             self.status.code(),
             self.status.reason_phrase(),
             self.headers.response_string()
-        );
-        let mut bytes = Vec::from(response.as_bytes());
-        if let Some(entity) = &self.entity {
-            for byte in entity {
-                bytes.push(*byte);
-            }
+        )?;
+
+        let mut body = CountingWriter {
+            inner: &mut writer,
+            written: 0,
+        };
+        match entity {
+            _ if !send_body => {}
+            Some(entity) if chunked => entity.write_chunked_to(&mut body)?,
+            Some(entity) => entity.write_to(&mut body)?,
+            None => {}
         }
+        let written = body.written;
+
+        writer.flush()?;
+        Ok(written)
+    }
+
+    fn may_have_body(&self) -> bool {
+        !matches!(self.status.code(), 100..=199 | 204 | 304)
+    }
+}
+
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    written: u64,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        stream.write_all(&bytes).unwrap();
+    fn written(response: &mut HttpResponse) -> String {
+        let mut output = Vec::new();
+        response.write(&mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity("hello", "text/plain");
+
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Type: text/plain\r\n\r\nhello",
+            written(&mut response)
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_write_json() {
+        let mut response = HttpResponse::new(HttpStatus::Created);
+        response.json(&vec![("id", 7)]).unwrap();
+
+        assert_eq!(
+            "HTTP/1.1 201 Created\r\nContent-Length: 10\r\nContent-Type: application/json\r\n\r\n[[\"id\",7]]",
+            written(&mut response)
+        );
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let mut response = HttpResponse::new(HttpStatus::NoContent);
+        response.set_cookie(&SetCookie::new("a", "1").unwrap().path("/"));
+        response.set_cookie(&SetCookie::new("b", "2").unwrap().http_only(true));
+
+        assert_eq!(
+            "HTTP/1.1 204 No Content\r\nSet-Cookie: a=1; Path=/\r\nSet-Cookie: b=2; HttpOnly\r\n\r\n",
+            written(&mut response)
+        );
+    }
+
+    #[test]
+    fn test_write_head() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity("hello", "text/plain");
+
+        let mut output = Vec::new();
+        assert_eq!(0, response.write_head(&mut output).unwrap());
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nContent-Type: text/plain\r\n\r\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_returns_entity_size() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity("hello", "text/plain");
+        assert_eq!(5, response.write(&mut Vec::new()).unwrap());
+
+        let mut response = HttpResponse::new(HttpStatus::NoContent);
+        assert_eq!(0, response.write(&mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn test_write_empty() {
+        let mut response = HttpResponse::new(HttpStatus::NotFound);
+        assert_eq!(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            written(&mut response)
+        );
+
+        let mut response = HttpResponse::new(HttpStatus::NoContent);
+        assert_eq!("HTTP/1.1 204 No Content\r\n\r\n", written(&mut response));
+    }
+
+    #[test]
+    fn test_replacing_entity() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity("first", "text/plain");
+        response.str_entity("second!", "text/html");
+
+        assert_eq!(Some("7"), response.headers.get_first("Content-Length"));
+        assert_eq!(
+            Some(1),
+            response.headers.get("Content-Type").map(|v| v.len())
+        );
+    }
+
+    #[test]
+    fn test_write_reader_with_length() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.reader_entity("report".as_bytes(), Some(6), "text/csv");
+
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nContent-Type: text/csv\r\n\r\nreport",
+            written(&mut response)
+        );
+    }
+
+    #[test]
+    fn test_write_chunked() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.chunked_entity(vec![b"ab".to_vec(), b"cde".to_vec()], "text/plain");
+
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n\
+             2\r\nab\r\n3\r\ncde\r\n0\r\n\r\n",
+            written(&mut response)
+        );
+    }
+
+    #[test]
+    fn test_write_unknown_length_on_http_one() {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.version = HttpVersion::One;
+        response.reader_entity("stream".as_bytes(), None, "text/plain");
+
+        assert_eq!(
+            "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\nstream",
+            written(&mut response)
+        );
     }
 }
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::mem;
+use std::path::Path;
+
+use tempfile::NamedTempFile;
+
+#[derive(Debug)]
+pub(crate) enum Spooled {
+    Memory(Vec<u8>),
+    TempFile { file: NamedTempFile, length: u64 },
+}
+
+// Buffers in memory up to `threshold` bytes and moves everything to a
+// temporary file once more arrives, so memory use never exceeds the threshold.
+pub(crate) struct Spool {
+    content: Spooled,
+    threshold: usize,
+}
+
+impl Spooled {
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Memory(bytes) => bytes.len() as u64,
+            Self::TempFile { length, .. } => *length,
+        }
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self, Self::Memory(_))
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Memory(bytes) => Some(bytes),
+            Self::TempFile { .. } => None,
+        }
+    }
+
+    pub fn reader(&self) -> io::Result<Box<dyn Read + Send + '_>> {
+        match self {
+            Self::Memory(bytes) => Ok(Box::new(Cursor::new(bytes.as_slice()))),
+            Self::TempFile { file, .. } => {
+                let mut file = file.reopen()?;
+                file.rewind()?;
+                Ok(Box::new(file))
+            }
+        }
+    }
+
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.reader()?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn persist(self, path: &Path) -> io::Result<File> {
+        match self {
+            Self::Memory(bytes) => {
+                let mut file = File::create(path)?;
+                file.write_all(&bytes)?;
+                Ok(file)
+            }
+            Self::TempFile { file, .. } => file.persist(path).map_err(|e| e.error),
+        }
+    }
+}
+
+impl Spool {
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            content: Spooled::Memory(Vec::new()),
+            threshold,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.content.len()
+    }
+
+    pub fn finish(mut self) -> io::Result<Spooled> {
+        self.flush()?;
+        Ok(self.content)
+    }
+}
+
+impl Write for Spool {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.content {
+            Spooled::Memory(bytes) if buf.len() <= self.threshold - bytes.len() => {
+                bytes.extend_from_slice(buf);
+            }
+            Spooled::Memory(bytes) => {
+                let bytes = mem::take(bytes);
+                let mut file = NamedTempFile::new()?;
+                file.write_all(&bytes)?;
+                file.write_all(buf)?;
+                self.content = Spooled::TempFile {
+                    file,
+                    length: (bytes.len() + buf.len()) as u64,
+                };
+            }
+            Spooled::TempFile { file, length } => {
+                file.write_all(buf)?;
+                *length += buf.len() as u64;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.content {
+            Spooled::Memory(_) => Ok(()),
+            Spooled::TempFile { file, .. } => file.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_bounded_by_threshold() {
+        let mut spool = Spool::new(16);
+
+        spool.write_all(&[b'a'; 10]).unwrap();
+        spool.write_all(&[b'b'; 6]).unwrap();
+        assert!(spool.content.is_in_memory());
+
+        spool.write_all(b"c").unwrap();
+        for _ in 0..1000 {
+            spool.write_all(&[b'd'; 64]).unwrap();
+            assert!(matches!(&spool.content, Spooled::TempFile { .. }));
+        }
+
+        let content = spool.finish().unwrap();
+        assert_eq!(17 + 64_000, content.len());
+        assert_eq!(None, content.as_bytes());
+
+        let bytes = content.bytes().unwrap();
+        assert_eq!(b"aaaaaaaaaabbbbbbc", &bytes[..17]);
+        assert!(bytes[17..].iter().all(|&b| b == b'd'));
+    }
+}
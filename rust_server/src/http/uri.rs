@@ -0,0 +1,356 @@
+use linked_hash_map::LinkedHashMap;
+
+use super::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UriForm {
+    Origin,
+    Absolute,
+    Authority,
+    Asterisk,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uri {
+    pub target: String,
+    pub form: UriForm,
+    pub scheme: Option<String>,
+    pub authority: Option<String>,
+    pub path: String,
+    pub segments: Vec<String>,
+    pub raw_path: String,
+    pub raw_query: Option<String>,
+    pub query: QueryParams,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryParams {
+    params: LinkedHashMap<String, Vec<String>>,
+}
+
+impl Uri {
+    pub fn parse(target: &str) -> Result<Self, ParseError> {
+        if target == "*" {
+            return Ok(Self::empty(UriForm::Asterisk, "*"));
+        }
+
+        let original = target;
+        let target = target.split_once('#').map_or(target, |(target, _)| target);
+        let (form, scheme, authority, rest) = match target.split_once("://") {
+            Some((scheme, rest)) if is_scheme(scheme) => {
+                let path_start = rest.find(['/', '?']).unwrap_or(rest.len());
+                let (authority, rest) = rest.split_at(path_start);
+                if authority.is_empty() {
+                    return Err(invalid(target));
+                }
+                (
+                    UriForm::Absolute,
+                    Some(scheme.to_ascii_lowercase()),
+                    Some(authority.to_owned()),
+                    rest,
+                )
+            }
+            _ if target.starts_with('/') => (UriForm::Origin, None, None, target),
+            _ if is_authority(target) => {
+                let mut uri = Self::empty(UriForm::Authority, "");
+                uri.target = original.to_owned();
+                uri.authority = Some(target.to_owned());
+                return Ok(uri);
+            }
+            _ => return Err(invalid(target)),
+        };
+
+        let (raw_path, raw_query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        let raw_path = if raw_path.is_empty() { "/" } else { raw_path };
+        if raw_path.bytes().any(|b| b.is_ascii_control() || b == b' ') {
+            return Err(invalid(target));
+        }
+
+        let segments = normalize_segments(raw_path).ok_or_else(|| invalid(target))?;
+        let encoded = segments
+            .iter()
+            .map(|s| encode_separators(s))
+            .collect::<Vec<_>>();
+        let mut path = format!("/{}", encoded.join("/"));
+        if !segments.is_empty() && ends_with_directory(raw_path) {
+            path.push('/');
+        }
+
+        Ok(Self {
+            target: original.to_owned(),
+            form,
+            scheme,
+            authority,
+            path,
+            segments,
+            raw_path: raw_path.to_owned(),
+            raw_query: raw_query.map(str::to_owned),
+            query: raw_query.map(QueryParams::parse).unwrap_or_default(),
+        })
+    }
+
+    fn empty(form: UriForm, path: &str) -> Self {
+        Self {
+            target: path.to_owned(),
+            form,
+            scheme: None,
+            authority: None,
+            path: path.to_owned(),
+            segments: Vec::new(),
+            raw_path: path.to_owned(),
+            raw_query: None,
+            query: QueryParams::new(),
+        }
+    }
+}
+
+impl QueryParams {
+    pub fn new() -> Self {
+        Self {
+            params: LinkedHashMap::new(),
+        }
+    }
+
+    pub fn parse(query: &str) -> Self {
+        let mut params = Self::new();
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.put(&decode_form_component(key), &decode_form_component(value));
+        }
+
+        params
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn put(&mut self, key: &str, value: &str) {
+        self.params
+            .entry(key.to_owned())
+            .or_default()
+            .push(value.to_owned());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[String]> {
+        self.params.get(key).map(Vec::as_slice)
+    }
+
+    pub fn get_first(&self, key: &str) -> Option<&str> {
+        self.get(key)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.params.contains_key(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.params.iter()
+    }
+}
+
+pub fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+pub fn percent_encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+pub fn decode_form_component(input: &str) -> String {
+    let input = input.replace('+', " ");
+    percent_decode(&input).unwrap_or(input)
+}
+
+fn normalize_segments(raw_path: &str) -> Option<Vec<String>> {
+    let mut segments: Vec<String> = Vec::new();
+
+    for segment in raw_path.split('/') {
+        match percent_decode(segment)?.as_str() {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment.to_owned()),
+        }
+    }
+
+    Some(segments)
+}
+
+// Keeps `%2F` from turning into a path separator once segments are joined
+// back into `path`. `%` is escaped too so the result stays unambiguous.
+fn encode_separators(segment: &str) -> String {
+    segment.replace('%', "%25").replace('/', "%2F")
+}
+
+fn ends_with_directory(raw_path: &str) -> bool {
+    let last = raw_path.rsplit('/').next().unwrap_or_default();
+    matches!(percent_decode(last).as_deref(), Some("" | "." | ".."))
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn is_authority(target: &str) -> bool {
+    match target.rsplit_once(':') {
+        Some((host, port)) => {
+            !host.is_empty()
+                && !host.contains(['/', '?', '#', '@'])
+                && !port.is_empty()
+                && port.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+fn invalid(target: &str) -> ParseError {
+    ParseError::InvalidUri(target.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_form() {
+        let uri = Uri::parse("/files/my%20report.pdf?download=1&tag=a+b&tag=c").unwrap();
+
+        assert_eq!(UriForm::Origin, uri.form);
+        assert_eq!(
+            "/files/my%20report.pdf?download=1&tag=a+b&tag=c",
+            uri.target
+        );
+        assert_eq!("/files/my report.pdf", uri.path);
+        assert_eq!(vec!["files", "my report.pdf"], uri.segments);
+        assert_eq!("/files/my%20report.pdf", uri.raw_path);
+        assert_eq!(Some("1"), uri.query.get_first("download"));
+        assert_eq!(
+            Some(&[String::from("a b"), String::from("c")][..]),
+            uri.query.get("tag")
+        );
+        assert_eq!(
+            vec!["download", "tag"],
+            uri.query
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!("/", Uri::parse("/").unwrap().path);
+        assert_eq!("/a/c", Uri::parse("//a/./b/../c").unwrap().path);
+        assert_eq!("/a/", Uri::parse("/a/b/..").unwrap().path);
+        assert_eq!("/dir/", Uri::parse("/dir/").unwrap().path);
+        assert_eq!("/", Uri::parse("/../../").unwrap().path);
+        assert_eq!("/a+b", Uri::parse("/a+b").unwrap().path);
+    }
+
+    #[test]
+    fn test_encoded_slash() {
+        let uri = Uri::parse("/a%2Fb").unwrap();
+
+        assert_eq!(vec!["a/b"], uri.segments);
+        assert_eq!("/a%2Fb", uri.path);
+        assert_eq!("/100%25", Uri::parse("/100%25").unwrap().path);
+        assert_ne!(
+            Uri::parse("/a%2Fb").unwrap().segments,
+            Uri::parse("/a/b").unwrap().segments
+        );
+    }
+
+    #[test]
+    fn test_absolute_form() {
+        let uri = Uri::parse("HTTP://example.com:8080/a%2Fb?x=1").unwrap();
+
+        assert_eq!(UriForm::Absolute, uri.form);
+        assert_eq!(Some("http"), uri.scheme.as_deref());
+        assert_eq!(Some("example.com:8080"), uri.authority.as_deref());
+        assert_eq!(vec!["a/b"], uri.segments);
+        assert_eq!("/a%2Fb", uri.path);
+        assert_eq!(Some("1"), uri.query.get_first("x"));
+
+        assert_eq!("/", Uri::parse("http://example.com").unwrap().path);
+    }
+
+    #[test]
+    fn test_asterisk_and_authority_forms() {
+        let uri = Uri::parse("*").unwrap();
+        assert_eq!(UriForm::Asterisk, uri.form);
+        assert_eq!("*", uri.path);
+
+        let uri = Uri::parse("example.com:443").unwrap();
+        assert_eq!(UriForm::Authority, uri.form);
+        assert_eq!(Some("example.com:443"), uri.authority.as_deref());
+        assert_eq!("example.com:443", uri.target);
+    }
+
+    #[test]
+    fn test_invalid() {
+        for target in [
+            "/bad%zz",
+            "/bad%+f",
+            "/bad%c3%28",
+            "relative/path",
+            "http:///path",
+            "/a b",
+        ] {
+            assert!(Uri::parse(target).is_err(), "{target}");
+        }
+    }
+
+    #[test]
+    fn test_query_edge_cases() {
+        let query = QueryParams::parse("flag&empty=&=nokey&bad=%zz");
+
+        assert_eq!(Some(""), query.get_first("flag"));
+        assert_eq!(Some(""), query.get_first("empty"));
+        assert_eq!(Some("nokey"), query.get_first(""));
+        assert_eq!(Some("%zz"), query.get_first("bad"));
+        assert!(!query.contains("missing"));
+    }
+}
@@ -1,2 +1,6 @@
+pub mod compression;
 pub mod http;
+pub mod router;
+pub mod server;
+pub mod static_files;
 pub mod thread_pool;
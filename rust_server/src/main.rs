@@ -1,62 +1,64 @@
 use std::{
-    fs,
-    io::BufReader,
-    net::{TcpListener, TcpStream},
+    net::TcpListener,
+    path::Path,
+    sync::Arc,
     thread,
     time::Duration,
 };
 
 use rust_server::{
-    http::{HttpMethod, HttpRequest, HttpResponse, HttpStatus},
-    thread_pool::ThreadPool,
+    compression::Compression,
+    http::{HttpRequest, HttpResponse, HttpStatus},
+    router::Router,
+    server::{AccessLog, LogFormat, Server},
+    static_files::{self, StaticFiles},
+    thread_pool::{OverflowPolicy, ThreadPoolBuilder},
 };
 
 fn main() {
     let listener = TcpListener::bind("localhost:7878").unwrap();
-    let pool = ThreadPool::new(4);
+    let pool = ThreadPoolBuilder::new()
+        .name_prefix("http-worker")
+        .min_workers(2)
+        .max_workers(8)
+        .queue_capacity(64)
+        .overflow_policy(OverflowPolicy::Reject)
+        .build()
+        .unwrap();
+    let mut server = Server::new(build_router());
+    server.access_log(AccessLog::stdout(LogFormat::Combined));
+    server.shutdown_handle().register_signals().unwrap();
 
     println!("Listening on port 7878...");
 
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                pool.execute(|| {
-                    println!("New connection established.");
-                    handle_connection(stream);
-                });
-            }
-            Err(e) => {
-                println!("Error in connection attempt: {}", e);
-            }
-        }
-    }
+    Arc::new(server).serve(listener, pool).unwrap();
     println!("Server shutting down...");
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let mut buf_reader = BufReader::new(&mut stream);
-
-    let request = HttpRequest::build(&mut buf_reader).unwrap();
-
-    let (status, filename) = match (request.method, request.path.as_str()) {
-        (HttpMethod::GET, "/") => (HttpStatus::Ok, "hello.html"),
-        (HttpMethod::GET, "/sleep") => {
-            thread::sleep(Duration::from_secs(5));
-            (HttpStatus::Ok, "sleepy.html")
-        },
-        (_, "/echo") => {
-            let body = request.raw_request;
-            let mut response = HttpResponse::new(HttpStatus::Ok);
-            response.str_entity(&body, "text/plain; charset=utf-8");
-            response.write(stream);
-            return;
-        },
-        _ => (HttpStatus::NotFound, "404.html"),
-    };
-
-    let contents = fs::read_to_string(format!("res/{filename}")).unwrap();
-    let mut response = HttpResponse::new(status);
-    response.str_entity(&contents, "text/html; charset=utf-8");
-
-    response.write(stream);
+fn build_router() -> Router {
+    let mut router = Router::new();
+    router.middleware(Compression::new());
+
+    router.get("/", |_: &HttpRequest| html_file(HttpStatus::Ok, "hello.html"));
+    router.get("/sleep", |_: &HttpRequest| {
+        thread::sleep(Duration::from_secs(5));
+        html_file(HttpStatus::Ok, "sleepy.html")
+    });
+    router.any("/echo", |request: &HttpRequest| {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity(&request.raw_request, "text/plain; charset=utf-8");
+        response
+    });
+    router.get("/res/*", StaticFiles::new("/res", "res").unwrap());
+    router.fallback(|_: &HttpRequest| html_file(HttpStatus::NotFound, "404.html"));
+
+    router
+}
+
+fn html_file(status: HttpStatus, filename: &str) -> HttpResponse {
+    let mut response = static_files::serve_file(&Path::new("res").join(filename));
+    if response.status == HttpStatus::Ok {
+        response.status = status;
+    }
+    response
 }
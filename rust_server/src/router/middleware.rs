@@ -0,0 +1,113 @@
+use crate::http::{HttpRequest, HttpResponse};
+
+pub trait Middleware: Send + Sync {
+    fn handle(&self, request: &mut HttpRequest, next: Next<'_>) -> HttpResponse;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&mut HttpRequest, Next<'_>) -> HttpResponse + Send + Sync,
+{
+    fn handle(&self, request: &mut HttpRequest, next: Next<'_>) -> HttpResponse {
+        self(request, next)
+    }
+}
+
+pub struct Next<'a> {
+    stack: &'a [Box<dyn Middleware>],
+    endpoint: &'a dyn Fn(&mut HttpRequest) -> HttpResponse,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        stack: &'a [Box<dyn Middleware>],
+        endpoint: &'a dyn Fn(&mut HttpRequest) -> HttpResponse,
+    ) -> Self {
+        Self { stack, endpoint }
+    }
+
+    pub fn run(self, request: &mut HttpRequest) -> HttpResponse {
+        match self.stack.split_first() {
+            Some((middleware, rest)) => middleware.handle(request, Next::new(rest, self.endpoint)),
+            None => (self.endpoint)(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpStatus;
+    use std::sync::{Arc, Mutex};
+
+    fn request() -> HttpRequest {
+        HttpRequest::build(&mut "GET / HTTP/1.1\r\n\r\n".as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_runs_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let trace = |name: &'static str| -> Box<dyn Middleware> {
+            let calls = Arc::clone(&calls);
+            Box::new(move |request: &mut HttpRequest, next: Next| {
+                calls.lock().unwrap().push(format!("{name} before"));
+                let response = next.run(request);
+                calls.lock().unwrap().push(format!("{name} after"));
+                response
+            })
+        };
+        let stack = vec![trace("outer"), trace("inner")];
+        let endpoint = |_: &mut HttpRequest| {
+            calls.lock().unwrap().push(String::from("handler"));
+            HttpResponse::new(HttpStatus::Ok)
+        };
+
+        Next::new(&stack, &endpoint).run(&mut request());
+
+        assert_eq!(
+            vec![
+                "outer before",
+                "inner before",
+                "handler",
+                "inner after",
+                "outer after"
+            ],
+            *calls.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let stack: Vec<Box<dyn Middleware>> = vec![
+            Box::new(|_: &mut HttpRequest, _: Next| HttpResponse::new(HttpStatus::Unauthorized)),
+            Box::new(|_: &mut HttpRequest, _: Next| -> HttpResponse { panic!("should not run") }),
+        ];
+        let endpoint = |_: &mut HttpRequest| -> HttpResponse { panic!("should not run") };
+
+        let response = Next::new(&stack, &endpoint).run(&mut request());
+        assert_eq!(HttpStatus::Unauthorized, response.status);
+    }
+
+    #[test]
+    fn test_modifies_request_and_response() {
+        let stack: Vec<Box<dyn Middleware>> =
+            vec![Box::new(|request: &mut HttpRequest, next: Next| {
+                request.headers.put("X-User", "ada");
+                let mut response = next.run(request);
+                response.headers.put("X-Handled-By", "middleware");
+                response
+            })];
+        let endpoint = |request: &mut HttpRequest| {
+            let mut response = HttpResponse::new(HttpStatus::Ok);
+            response.str_entity(request.headers.get_first("X-User").unwrap(), "text/plain");
+            response
+        };
+
+        let response = Next::new(&stack, &endpoint).run(&mut request());
+        assert_eq!(
+            Some("middleware"),
+            response.headers.get_first("X-Handled-By")
+        );
+        assert_eq!(Some("3"), response.headers.get_first("Content-Length"));
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use crate::http::{CacheControl, HttpMethod, HttpRequest, HttpResponse, HttpStatus};
+
+mod middleware;
+mod pattern;
+
+pub use middleware::{Middleware, Next};
+use pattern::PathPattern;
+
+pub trait Handler: Send + Sync {
+    fn handle(&self, request: &HttpRequest) -> HttpResponse;
+}
+
+impl<F> Handler for F
+where
+    F: Fn(&HttpRequest) -> HttpResponse + Send + Sync,
+{
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        self(request)
+    }
+}
+
+pub struct Route {
+    method: Option<HttpMethod>,
+    pattern: PathPattern,
+    handler: Box<dyn Handler>,
+    cache_control: Option<CacheControl>,
+    produces: Vec<String>,
+    middlewares: Vec<Box<dyn Middleware>>,
+}
+
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Box<dyn Handler>,
+    middlewares: Vec<Box<dyn Middleware>>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: Box::new(not_found),
+            middlewares: Vec::new(),
+        }
+    }
+
+    pub fn route<H>(&mut self, method: HttpMethod, pattern: &str, handler: H) -> &mut Route
+    where
+        H: Handler + 'static,
+    {
+        self.add_route(Some(method), pattern, handler)
+    }
+
+    pub fn any<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.add_route(None, pattern, handler)
+    }
+
+    fn add_route<H>(&mut self, method: Option<HttpMethod>, pattern: &str, handler: H) -> &mut Route
+    where
+        H: Handler + 'static,
+    {
+        self.routes.push(Route {
+            method,
+            pattern: PathPattern::parse(pattern),
+            handler: Box::new(handler),
+            cache_control: None,
+            produces: Vec::new(),
+            middlewares: Vec::new(),
+        });
+
+        self.routes.last_mut().unwrap()
+    }
+
+    pub fn get<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.route(HttpMethod::GET, pattern, handler)
+    }
+
+    pub fn post<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.route(HttpMethod::POST, pattern, handler)
+    }
+
+    pub fn put<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.route(HttpMethod::PUT, pattern, handler)
+    }
+
+    pub fn patch<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.route(HttpMethod::PATCH, pattern, handler)
+    }
+
+    pub fn delete<H: Handler + 'static>(&mut self, pattern: &str, handler: H) -> &mut Route {
+        self.route(HttpMethod::DELETE, pattern, handler)
+    }
+
+    pub fn fallback<H: Handler + 'static>(&mut self, handler: H) {
+        self.fallback = Box::new(handler);
+    }
+
+    pub fn middleware<M: Middleware + 'static>(&mut self, middleware: M) -> &mut Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    pub fn handle(&self, request: &mut HttpRequest) -> HttpResponse {
+        let dispatch = |request: &mut HttpRequest| self.dispatch(request);
+        Next::new(&self.middlewares, &dispatch).run(request)
+    }
+
+    fn dispatch(&self, request: &mut HttpRequest) -> HttpResponse {
+        let segments = request.uri.segments.clone();
+        let mut allowed_methods: Vec<&HttpMethod> = Vec::new();
+        let mut get_for_head = None;
+
+        for route in &self.routes {
+            let Some(params) = route.pattern.matches(&segments) else {
+                continue;
+            };
+
+            match &route.method {
+                Some(HttpMethod::GET) if request.method == HttpMethod::HEAD => {
+                    if get_for_head.is_none() {
+                        get_for_head = Some((route, params));
+                    }
+                }
+                Some(method) if *method != request.method => {
+                    if !allowed_methods.contains(&method) {
+                        allowed_methods.push(method);
+                    }
+                }
+                _ => return self.run_route(route, params, request),
+            }
+        }
+
+        // HEAD falls back to the GET handler; the server drops the body when writing.
+        if let Some((route, params)) = get_for_head {
+            return self.run_route(route, params, request);
+        }
+
+        if allowed_methods.is_empty() {
+            return self.fallback.handle(request);
+        }
+        if allowed_methods.contains(&&HttpMethod::GET)
+            && !allowed_methods.contains(&&HttpMethod::HEAD)
+        {
+            allowed_methods.push(&HttpMethod::HEAD);
+        }
+
+        let allow = allowed_methods
+            .iter()
+            .map(|method| method.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut response = HttpResponse::new(HttpStatus::MethodNotAllowed);
+        response.headers.put("Allow", &allow);
+        response
+    }
+
+    fn run_route(
+        &self,
+        route: &Route,
+        params: HashMap<String, String>,
+        request: &mut HttpRequest,
+    ) -> HttpResponse {
+        request.path_params = params;
+        let endpoint = |request: &mut HttpRequest| route.handle(request);
+        Next::new(&route.middlewares, &endpoint).run(request)
+    }
+}
+
+impl Route {
+    pub fn cache_control(&mut self, policy: CacheControl) -> &mut Self {
+        self.cache_control = Some(policy);
+        self
+    }
+
+    pub fn produces(&mut self, media_types: &[&str]) -> &mut Self {
+        self.produces = media_types
+            .iter()
+            .map(|&media_type| media_type.to_owned())
+            .collect();
+        self
+    }
+
+    pub fn middleware<M: Middleware + 'static>(&mut self, middleware: M) -> &mut Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        if !self.produces.is_empty() {
+            let offered = self.produces.iter().map(String::as_str).collect::<Vec<_>>();
+            let mut response = match request.preferred_media_type(&offered) {
+                Some(_) => self.handle_acceptable(request),
+                None => not_acceptable(&offered),
+            };
+            response.headers.put_list_item("Vary", "Accept");
+            return response;
+        }
+
+        self.handle_acceptable(request)
+    }
+
+    fn handle_acceptable(&self, request: &HttpRequest) -> HttpResponse {
+        let mut response = self.handler.handle(request);
+
+        if let Some(policy) = &self.cache_control {
+            if response.status.code() < 400 && response.headers.get("Cache-Control").is_none() {
+                response.cache_control(policy);
+            }
+        }
+
+        response
+    }
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn not_found(_: &HttpRequest) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpStatus::NotFound);
+    response.str_entity("Not Found", "text/plain; charset=utf-8");
+    response
+}
+
+fn not_acceptable(offered: &[&str]) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpStatus::NotAcceptable);
+    response.str_entity(
+        &format!("Available media types: {}", offered.join(", ")),
+        "text/plain; charset=utf-8",
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::HttpEntity;
+    use std::time::Duration;
+
+    fn request(raw: &str) -> HttpRequest {
+        HttpRequest::build(&mut raw.as_bytes()).unwrap()
+    }
+
+    fn text(body: &str) -> HttpResponse {
+        let mut response = HttpResponse::new(HttpStatus::Ok);
+        response.str_entity(body, "text/plain");
+        response
+    }
+
+    fn entity(response: &HttpResponse) -> &[u8] {
+        response
+            .entity
+            .as_ref()
+            .and_then(HttpEntity::as_bytes)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_dispatch_by_method_and_path() {
+        let mut router = Router::new();
+        router.get("/", |_: &HttpRequest| text("index"));
+        router.post("/", |_: &HttpRequest| text("posted"));
+
+        let response = router.handle(&mut request("GET / HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"index", entity(&response));
+
+        let response = router.handle(&mut request("POST / HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"posted", entity(&response));
+    }
+
+    #[test]
+    fn test_path_params() {
+        let mut router = Router::new();
+        router.get("/users/:id", |request: &HttpRequest| {
+            text(request.path_param("id").unwrap())
+        });
+
+        let response = router.handle(&mut request("GET /users/42?full=true HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::Ok, response.status);
+        assert_eq!(b"42", entity(&response));
+
+        let response = router.handle(&mut request("GET /users/a%2Fb HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"a/b", entity(&response));
+        let response = router.handle(&mut request("GET /users/a/b HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::NotFound, response.status);
+    }
+
+    #[test]
+    fn test_any_method() {
+        let mut router = Router::new();
+        router.any("/echo", |request: &HttpRequest| {
+            text(request.method.as_str())
+        });
+
+        let response = router.handle(&mut request("PATCH /echo HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"PATCH", entity(&response));
+    }
+
+    #[test]
+    fn test_first_registered_route_wins() {
+        let mut router = Router::new();
+        router.get("/users/me", |_: &HttpRequest| text("me"));
+        router.get("/users/:id", |_: &HttpRequest| text("someone"));
+
+        let response = router.handle(&mut request("GET /users/me HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"me", entity(&response));
+    }
+
+    #[test]
+    fn test_not_found() {
+        let mut router = Router::new();
+        router.get("/", |_: &HttpRequest| text("index"));
+
+        let response = router.handle(&mut request("GET /nope HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::NotFound, response.status);
+
+        router.fallback(|_: &HttpRequest| text("custom"));
+        let response = router.handle(&mut request("GET /nope HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"custom", entity(&response));
+    }
+
+    #[test]
+    fn test_route_cache_control() {
+        let mut router = Router::new();
+        router
+            .get("/cached", |_: &HttpRequest| text("cached"))
+            .cache_control(CacheControl::public(Duration::from_secs(60)));
+        router
+            .get("/custom", |_: &HttpRequest| {
+                let mut response = text("custom");
+                response.cache_control(&CacheControl::no_store());
+                response
+            })
+            .cache_control(CacheControl::public(Duration::from_secs(60)));
+        router
+            .get("/missing", |_: &HttpRequest| {
+                HttpResponse::new(HttpStatus::NotFound)
+            })
+            .cache_control(CacheControl::public(Duration::from_secs(60)));
+
+        let response = router.handle(&mut request("GET /cached HTTP/1.1\r\n\r\n"));
+        assert_eq!(
+            Some("public, max-age=60"),
+            response.headers.get_first("Cache-Control")
+        );
+
+        let response = router.handle(&mut request("GET /custom HTTP/1.1\r\n\r\n"));
+        assert_eq!(
+            Some("no-store"),
+            response.headers.get_first("Cache-Control")
+        );
+
+        let response = router.handle(&mut request("GET /missing HTTP/1.1\r\n\r\n"));
+        assert_eq!(None, response.headers.get_first("Cache-Control"));
+    }
+
+    #[test]
+    fn test_middleware() {
+        let mut router = Router::new();
+        router.middleware(|request: &mut HttpRequest, next: Next| {
+            let mut response = next.run(request);
+            response.headers.put("X-Global", "yes");
+            response
+        });
+        router.get("/open", |_: &HttpRequest| text("open"));
+        router
+            .get("/admin", |_: &HttpRequest| text("secret"))
+            .middleware(|request: &mut HttpRequest, next: Next| {
+                match request.headers.get_first("Authorization") {
+                    Some(_) => next.run(request),
+                    None => HttpResponse::new(HttpStatus::Unauthorized),
+                }
+            });
+
+        let response = router.handle(&mut request("GET /open HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"open", entity(&response));
+        assert_eq!(Some("yes"), response.headers.get_first("X-Global"));
+
+        let response = router.handle(&mut request("GET /admin HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::Unauthorized, response.status);
+        assert_eq!(Some("yes"), response.headers.get_first("X-Global"));
+
+        let response = router.handle(&mut request(
+            "GET /admin HTTP/1.1\r\nAuthorization: Basic x\r\n\r\n",
+        ));
+        assert_eq!(b"secret", entity(&response));
+
+        let response = router.handle(&mut request("GET /missing HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::NotFound, response.status);
+        assert_eq!(Some("yes"), response.headers.get_first("X-Global"));
+    }
+
+    #[test]
+    fn test_produces() {
+        let mut router = Router::new();
+        router
+            .get("/report", |request: &HttpRequest| {
+                match request.preferred_media_type(&["text/html", "application/json"]) {
+                    Some("application/json") => text("{}"),
+                    _ => text("<p>report</p>"),
+                }
+            })
+            .produces(&["text/html", "application/json"]);
+
+        let response = router.handle(&mut request(
+            "GET /report HTTP/1.1\r\nAccept: application/json, text/html;q=0.5\r\n\r\n",
+        ));
+        assert_eq!(b"{}", entity(&response));
+        assert_eq!(Some("Accept"), response.headers.get_first("Vary"));
+
+        let response = router.handle(&mut request("GET /report HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"<p>report</p>", entity(&response));
+
+        let response = router.handle(&mut request(
+            "GET /report HTTP/1.1\r\nAccept: image/png\r\n\r\n",
+        ));
+        assert_eq!(HttpStatus::NotAcceptable, response.status);
+        assert_eq!(Some("Accept"), response.headers.get_first("Vary"));
+    }
+
+    #[test]
+    fn test_method_not_allowed() {
+        let mut router = Router::new();
+        router.get("/items/:id", |_: &HttpRequest| text("get"));
+        router.delete("/items/:id", |_: &HttpRequest| text("delete"));
+        router.get("/items/*rest", |_: &HttpRequest| text("other"));
+
+        let response = router.handle(&mut request("PUT /items/1 HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::MethodNotAllowed, response.status);
+        assert_eq!(
+            Some("GET, DELETE, HEAD"),
+            response.headers.get_first("Allow")
+        );
+    }
+
+    #[test]
+    fn test_head_uses_get_handler() {
+        let mut router = Router::new();
+        router.get("/page", |_: &HttpRequest| text("page"));
+        router.get("/custom", |_: &HttpRequest| text("get"));
+        router.route(HttpMethod::HEAD, "/custom", |_: &HttpRequest| text("head"));
+
+        let response = router.handle(&mut request("HEAD /page HTTP/1.1\r\n\r\n"));
+        assert_eq!(HttpStatus::Ok, response.status);
+        assert_eq!(b"page", entity(&response));
+
+        let response = router.handle(&mut request("HEAD /custom HTTP/1.1\r\n\r\n"));
+        assert_eq!(b"head", entity(&response));
+    }
+}
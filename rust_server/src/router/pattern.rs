@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Static(String),
+    Param(String),
+    Wildcard(String),
+}
+
+#[derive(Debug)]
+pub struct PathPattern {
+    segments: Vec<Segment>,
+}
+
+impl PathPattern {
+    pub fn parse(pattern: &str) -> Self {
+        let raw_segments = split_path(pattern).collect::<Vec<_>>();
+        let mut segments = Vec::with_capacity(raw_segments.len());
+
+        for (i, segment) in raw_segments.iter().enumerate() {
+            let segment = if let Some(name) = segment.strip_prefix(':') {
+                assert!(
+                    !name.is_empty(),
+                    "Path parameter without a name in {pattern}"
+                );
+                Segment::Param(name.to_owned())
+            } else if let Some(name) = segment.strip_prefix('*') {
+                assert!(
+                    i == raw_segments.len() - 1,
+                    "Wildcard must be the last segment in {pattern}"
+                );
+                let name = if name.is_empty() { "*" } else { name };
+                Segment::Wildcard(name.to_owned())
+            } else {
+                Segment::Static((*segment).to_owned())
+            };
+
+            segments.push(segment);
+        }
+
+        Self { segments }
+    }
+
+    pub fn matches<S: AsRef<str>>(&self, path_segments: &[S]) -> Option<HashMap<String, String>> {
+        let path_segments = path_segments.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        let mut params = HashMap::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Wildcard(name) => {
+                    params.insert(
+                        name.clone(),
+                        path_segments[i.min(path_segments.len())..].join("/"),
+                    );
+                    return Some(params);
+                }
+                Segment::Static(expected) => {
+                    if path_segments.get(i) != Some(&expected.as_str()) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let value = path_segments.get(i)?;
+                    params.insert(name.clone(), (*value).to_owned());
+                }
+            }
+        }
+
+        if path_segments.len() == self.segments.len() {
+            Some(params)
+        } else {
+            None
+        }
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl PathPattern {
+        fn matches_path(&self, path: &str) -> Option<HashMap<String, String>> {
+            self.matches(&split_path(path).collect::<Vec<_>>())
+        }
+    }
+
+    #[test]
+    fn test_static_match() {
+        let pattern = PathPattern::parse("/hello/world");
+
+        assert_eq!(Some(HashMap::new()), pattern.matches_path("/hello/world"));
+        assert_eq!(Some(HashMap::new()), pattern.matches_path("/hello/world/"));
+        assert_eq!(None, pattern.matches_path("/hello"));
+        assert_eq!(None, pattern.matches_path("/hello/world/again"));
+    }
+
+    #[test]
+    fn test_root() {
+        let pattern = PathPattern::parse("/");
+
+        assert!(pattern.matches_path("/").is_some());
+        assert!(pattern.matches_path("/anything").is_none());
+    }
+
+    #[test]
+    fn test_params() {
+        let pattern = PathPattern::parse("/users/:id/posts/:post_id");
+        let params = pattern.matches_path("/users/42/posts/7").unwrap();
+
+        assert_eq!(Some("42"), params.get("id").map(String::as_str));
+        assert_eq!(Some("7"), params.get("post_id").map(String::as_str));
+        assert_eq!(None, pattern.matches_path("/users/42/posts"));
+
+        let params = pattern.matches(&["users", "a/b", "posts", "7"]).unwrap();
+        assert_eq!(Some("a/b"), params.get("id").map(String::as_str));
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let pattern = PathPattern::parse("/static/*file");
+
+        let params = pattern.matches_path("/static/css/main.css").unwrap();
+        assert_eq!(Some("css/main.css"), params.get("file").map(String::as_str));
+
+        let params = pattern.matches_path("/static").unwrap();
+        assert_eq!(Some(""), params.get("file").map(String::as_str));
+
+        let params = PathPattern::parse("/*").matches_path("/a/b").unwrap();
+        assert_eq!(Some("a/b"), params.get("*").map(String::as_str));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wildcard_not_last() {
+        PathPattern::parse("/static/*file/other");
+    }
+}
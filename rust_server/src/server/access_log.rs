@@ -0,0 +1,427 @@
+use std::{
+    fmt::Write as _,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::http::{HttpRequest, HttpVersion};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Common,
+    Combined,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    pub client: Option<SocketAddr>,
+    pub time: SystemTime,
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub version: Option<HttpVersion>,
+    pub status: u16,
+    pub bytes: u64,
+    pub referer: Option<String>,
+    pub user_agent: Option<String>,
+    pub latency: Duration,
+}
+
+pub struct AccessLog {
+    format: LogFormat,
+    sink: Mutex<Sink>,
+}
+
+enum Sink {
+    Stdout,
+    File(RotatingFile),
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+    max_files: usize,
+}
+
+impl AccessLogEntry {
+    pub fn new(
+        client: Option<SocketAddr>,
+        time: SystemTime,
+        request: Option<&HttpRequest>,
+    ) -> Self {
+        let header = |name: &str| {
+            request
+                .and_then(|request| request.headers.get_first(name))
+                .map(str::to_owned)
+        };
+
+        Self {
+            client,
+            time,
+            method: request.map(|request| request.method.to_string()),
+            path: request.map(|request| request.uri.target.clone()),
+            version: request.map(|request| request.version),
+            status: 0,
+            bytes: 0,
+            referer: header("Referer"),
+            user_agent: header("User-Agent"),
+            latency: Duration::ZERO,
+        }
+    }
+
+    pub fn format(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Common => self.common(),
+            LogFormat::Combined => format!(
+                "{} \"{}\" \"{}\"",
+                self.common(),
+                quoted_or_dash(self.referer.as_deref()),
+                quoted_or_dash(self.user_agent.as_deref())
+            ),
+            LogFormat::Json => self.json(),
+        }
+    }
+
+    fn common(&self) -> String {
+        let client = self
+            .client
+            .map_or_else(|| String::from("-"), |client| client.ip().to_string());
+        let bytes = match self.bytes {
+            0 => String::from("-"),
+            bytes => bytes.to_string(),
+        };
+
+        format!(
+            "{client} - - [{}] \"{}\" {} {bytes}",
+            clf_time(self.time),
+            escape_quoted(&self.request_line()),
+            self.status
+        )
+    }
+
+    fn request_line(&self) -> String {
+        match (&self.method, &self.path, &self.version) {
+            (Some(method), Some(path), Some(version)) => format!("{method} {path} {version}"),
+            _ => String::from("-"),
+        }
+    }
+
+    fn json(&self) -> String {
+        let mut line = String::from("{");
+        let mut field = |key: &str, value: Option<String>| {
+            if line.len() > 1 {
+                line.push(',');
+            }
+            let _ = write!(line, "\"{key}\":");
+            match value {
+                Some(value) => line.push_str(&value),
+                None => line.push_str("null"),
+            }
+        };
+        let string = |value: Option<&str>| value.map(|value| format!("\"{}\"", escape_json(value)));
+
+        field("time", string(Some(&rfc3339_time(self.time))));
+        field(
+            "client",
+            string(self.client.map(|client| client.ip().to_string()).as_deref()),
+        );
+        field("method", string(self.method.as_deref()));
+        field("path", string(self.path.as_deref()));
+        field(
+            "version",
+            string(self.version.map(|v| v.to_string()).as_deref()),
+        );
+        field("status", Some(self.status.to_string()));
+        field("bytes", Some(self.bytes.to_string()));
+        field("referer", string(self.referer.as_deref()));
+        field("user_agent", string(self.user_agent.as_deref()));
+        field("latency_us", Some(self.latency.as_micros().to_string()));
+
+        line.push('}');
+        line
+    }
+}
+
+impl AccessLog {
+    pub fn stdout(format: LogFormat) -> Self {
+        Self {
+            format,
+            sink: Mutex::new(Sink::Stdout),
+        }
+    }
+
+    pub fn file(path: impl AsRef<Path>, format: LogFormat) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            format,
+            sink: Mutex::new(Sink::File(RotatingFile {
+                path,
+                file,
+                size,
+                max_size: None,
+                max_files: 0,
+            })),
+        })
+    }
+
+    pub fn rotate(self, max_size: u64, max_files: usize) -> Self {
+        let mut sink = self.sink.into_inner().unwrap_or_else(|e| e.into_inner());
+        if let Sink::File(file) = &mut sink {
+            file.max_size = Some(max_size);
+            file.max_files = max_files;
+        }
+
+        Self {
+            format: self.format,
+            sink: Mutex::new(sink),
+        }
+    }
+
+    pub fn log(&self, entry: &AccessLogEntry) {
+        let mut line = entry.format(self.format);
+        line.push('\n');
+
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let result = match &mut *sink {
+            Sink::Stdout => io::stdout().lock().write_all(line.as_bytes()),
+            Sink::File(file) => file.write_line(line.as_bytes()),
+        };
+        if let Err(e) = result {
+            eprintln!("Error writing access log: {e}");
+        }
+    }
+}
+
+impl RotatingFile {
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if let Some(max_size) = self.max_size {
+            if self.size > 0 && self.size + line.len() as u64 > max_size {
+                self.rotate()?;
+            }
+        }
+
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                match fs::rename(self.rotated_path(index), self.rotated_path(index + 1)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn quoted_or_dash(value: Option<&str>) -> String {
+    value.map_or_else(|| String::from("-"), escape_quoted)
+}
+
+fn escape_quoted(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_ascii_control() => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn clf_time(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = utc_fields(time);
+    format!(
+        "{day:02}/{}/{year}:{hour:02}:{minute:02}:{second:02} +0000",
+        MONTHS[month as usize - 1]
+    )
+}
+
+fn rfc3339_time(time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) = utc_fields(time);
+    format!("{year}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
+
+fn utc_fields(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs()) as i64;
+    let (days, second_of_day) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+
+    (
+        year,
+        month,
+        day,
+        (second_of_day / 3600) as u32,
+        (second_of_day % 3600 / 60) as u32,
+        (second_of_day % 60) as u32,
+    )
+}
+
+// Howard Hinnant's days-to-civil algorithm for the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> AccessLogEntry {
+        let request = HttpRequest::build(
+            &mut "GET /index.html?q=1 HTTP/1.1\r\nReferer: http://example.com/\r\nUser-Agent: curl/8.0 \"x\"\r\n\r\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let mut entry = AccessLogEntry::new(
+            Some("127.0.0.1:5000".parse().unwrap()),
+            UNIX_EPOCH + Duration::from_secs(971_186_136),
+            Some(&request),
+        );
+        entry.status = 200;
+        entry.bytes = 2326;
+        entry.latency = Duration::from_micros(1500);
+        entry
+    }
+
+    #[test]
+    fn test_common_and_combined() {
+        let entry = entry();
+
+        assert_eq!(
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /index.html?q=1 HTTP/1.1\" 200 2326",
+            entry.format(LogFormat::Common)
+        );
+        assert_eq!(
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /index.html?q=1 HTTP/1.1\" 200 2326 \
+            \"http://example.com/\" \"curl/8.0 \\\"x\\\"\"",
+            entry.format(LogFormat::Combined)
+        );
+    }
+
+    #[test]
+    fn test_unparsed_request() {
+        let mut entry = AccessLogEntry::new(None, UNIX_EPOCH, None);
+        entry.status = 400;
+
+        assert_eq!(
+            "- - - [01/Jan/1970:00:00:00 +0000] \"-\" 400 - \"-\" \"-\"",
+            entry.format(LogFormat::Combined)
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            "{\"time\":\"2000-10-10T13:55:36Z\",\"client\":\"127.0.0.1\",\"method\":\"GET\",\
+            \"path\":\"/index.html?q=1\",\"version\":\"HTTP/1.1\",\"status\":200,\"bytes\":2326,\
+            \"referer\":\"http://example.com/\",\"user_agent\":\"curl/8.0 \\\"x\\\"\",\
+            \"latency_us\":1500}",
+            entry().format(LogFormat::Json)
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 2, 29), civil_from_days(11_016));
+        assert_eq!((1969, 12, 31), civil_from_days(-1));
+    }
+
+    #[test]
+    fn test_file_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("access.log");
+        let log = AccessLog::file(&path, LogFormat::Common)
+            .unwrap()
+            .rotate(150, 2);
+        let entry = entry();
+        let line_length = entry.format(LogFormat::Common).len() as u64 + 1;
+
+        for _ in 0..4 {
+            log.log(&entry);
+        }
+
+        assert_eq!(line_length, fs::metadata(&path).unwrap().len());
+        assert_eq!(
+            line_length,
+            fs::metadata(dir.path().join("access.log.1")).unwrap().len()
+        );
+        assert_eq!(
+            line_length,
+            fs::metadata(dir.path().join("access.log.2")).unwrap().len()
+        );
+        assert!(!dir.path().join("access.log.3").exists());
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+// Bounds the total time spent reading rather than the time between reads, so a
+// client trickling one byte at a time can't hold the connection open forever.
+pub struct DeadlineReader<'a> {
+    inner: &'a mut BufReader<TcpStream>,
+    deadline: Option<Instant>,
+}
+
+impl<'a> DeadlineReader<'a> {
+    pub fn new(inner: &'a mut BufReader<TcpStream>, timeout: Option<Duration>) -> io::Result<Self> {
+        if timeout.is_none() {
+            inner.get_ref().set_read_timeout(None)?;
+        }
+
+        Ok(Self {
+            inner,
+            deadline: timeout.map(|timeout| Instant::now() + timeout),
+        })
+    }
+
+    fn arm(&self) -> io::Result<()> {
+        let Some(deadline) = self.deadline else {
+            return Ok(());
+        };
+        if !self.inner.buffer().is_empty() {
+            return Ok(());
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ErrorKind::TimedOut.into());
+        }
+        self.inner.get_ref().set_read_timeout(Some(remaining))
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.arm()?;
+        self.inner.read(buf)
+    }
+}
+
+impl BufRead for DeadlineReader<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.arm()?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+    }
+}
@@ -0,0 +1,525 @@
+use anyhow::Result;
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind},
+    net::{SocketAddr, TcpListener, TcpStream},
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    http::{Headers, HttpMethod, HttpRequest, HttpResponse, HttpStatus, ParseError, ParserLimits},
+    router::Router,
+    thread_pool::ThreadPool,
+};
+
+mod access_log;
+mod deadline;
+mod shutdown;
+
+pub use access_log::{AccessLog, AccessLogEntry, LogFormat};
+pub use shutdown::ShutdownHandle;
+
+use deadline::DeadlineReader;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct Server {
+    router: Router,
+    keep_alive_timeout: Duration,
+    shutdown_timeout: Duration,
+    limits: ParserLimits,
+    shutdown: ShutdownHandle,
+    access_log: Option<AccessLog>,
+}
+
+impl Server {
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            keep_alive_timeout: Duration::from_secs(5),
+            shutdown_timeout: Duration::from_secs(30),
+            limits: ParserLimits::default(),
+            shutdown: ShutdownHandle::new(),
+            access_log: None,
+        }
+    }
+
+    pub fn keep_alive_timeout(&mut self, timeout: Duration) {
+        assert!(!timeout.is_zero());
+        self.keep_alive_timeout = timeout;
+    }
+
+    pub fn shutdown_timeout(&mut self, timeout: Duration) {
+        self.shutdown_timeout = timeout;
+    }
+
+    pub fn limits(&mut self, limits: ParserLimits) {
+        self.limits = limits;
+    }
+
+    pub fn access_log(&mut self, access_log: AccessLog) {
+        self.access_log = Some(access_log);
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    pub fn serve(self: Arc<Self>, listener: TcpListener, mut pool: ThreadPool) -> io::Result<()> {
+        listener.set_nonblocking(true)?;
+
+        while !self.shutdown.is_shutting_down() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    let overflow_stream = stream.try_clone()?;
+                    let server = Arc::clone(&self);
+                    let result = pool.execute(AssertUnwindSafe(move || {
+                        if let Err(e) = server.handle_connection(stream) {
+                            eprintln!("Error handling connection: {e}");
+                        }
+                    }));
+
+                    if let Err(e) = result {
+                        eprintln!("Rejecting connection: {e}");
+                        reject_connection(overflow_stream);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => eprintln!("Error in connection attempt: {e}"),
+            }
+        }
+
+        drop(listener);
+        let abandoned = pool.shutdown_timeout(self.shutdown_timeout);
+        if abandoned > 0 {
+            eprintln!("{abandoned} worker(s) did not finish before the shutdown deadline");
+        }
+
+        Ok(())
+    }
+
+    pub fn handle_connection(&self, stream: TcpStream) -> Result<()> {
+        let mut buf_reader = BufReader::new(stream.try_clone()?);
+        let client = stream.peer_addr().ok();
+        let mut writer = stream;
+
+        loop {
+            if !self.wait_for_request(&mut buf_reader)? {
+                return Ok(());
+            }
+            let started = (SystemTime::now(), Instant::now());
+
+            let mut request = match self.read_request(&mut buf_reader) {
+                Ok(request) => request,
+                Err(ParseError::Io(e)) if !is_timeout(&e) => return Err(e.into()),
+                Err(e) => {
+                    let mut response = bad_request_response(&e);
+                    let bytes = response.write(&mut writer)?;
+                    self.log_access(client, started, None, &response, bytes);
+                    return Ok(());
+                }
+            };
+            let mut response = self.router.handle(&mut request);
+            response.version = request.version;
+
+            let keep_alive = request.keep_alive()
+                && !closes_connection(&response.headers)
+                && !self.shutdown.is_shutting_down();
+            let connection = if keep_alive { "keep-alive" } else { "close" };
+            response.headers.set_all("Connection", &[connection]);
+
+            let bytes = if request.method == HttpMethod::HEAD {
+                response.write_head(&mut writer)?
+            } else {
+                response.write(&mut writer)?
+            };
+            self.log_access(client, started, Some(&request), &response, bytes);
+
+            if closes_connection(&response.headers) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn log_access(
+        &self,
+        client: Option<SocketAddr>,
+        (time, started): (SystemTime, Instant),
+        request: Option<&HttpRequest>,
+        response: &HttpResponse,
+        bytes: u64,
+    ) {
+        if let Some(access_log) = &self.access_log {
+            let mut entry = AccessLogEntry::new(client, time, request);
+            entry.status = response.status.code();
+            entry.bytes = bytes;
+            entry.latency = started.elapsed();
+            access_log.log(&entry);
+        }
+    }
+
+    fn wait_for_request(&self, buf_reader: &mut BufReader<TcpStream>) -> io::Result<bool> {
+        let idle_since = Instant::now();
+        buf_reader
+            .get_ref()
+            .set_read_timeout(Some(POLL_INTERVAL.min(self.keep_alive_timeout)))?;
+
+        let has_request = loop {
+            match buf_reader.fill_buf() {
+                Ok(buffer) => break !buffer.is_empty(),
+                Err(e) if is_timeout(&e) => {
+                    if self.shutdown.is_shutting_down()
+                        || idle_since.elapsed() >= self.keep_alive_timeout
+                    {
+                        break false;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        Ok(has_request)
+    }
+
+    fn read_request(
+        &self,
+        buf_reader: &mut BufReader<TcpStream>,
+    ) -> Result<HttpRequest, ParseError> {
+        let mut head_reader = DeadlineReader::new(buf_reader, self.limits.header_timeout)?;
+        let mut request = HttpRequest::read_head(&mut head_reader, &self.limits)?;
+
+        let mut body_reader = DeadlineReader::new(buf_reader, self.limits.body_timeout)?;
+        request.read_body(&mut body_reader, &self.limits)?;
+
+        Ok(request)
+    }
+}
+
+fn reject_connection(mut stream: TcpStream) {
+    let mut response = HttpResponse::new(HttpStatus::ServiceUnavailable);
+    response.headers.put("Retry-After", "1");
+    response.headers.put("Connection", "close");
+    let _ = response.write(&mut stream);
+}
+
+fn bad_request_response(error: &ParseError) -> HttpResponse {
+    let mut response = HttpResponse::new(error.status());
+    response.str_entity(&error.to_string(), "text/plain; charset=utf-8");
+    response.headers.put("Connection", "close");
+    response
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+fn closes_connection(headers: &Headers) -> bool {
+    headers
+        .get_splitting_commas("Connection")
+        .into_iter()
+        .flatten()
+        .any(|option| option.eq_ignore_ascii_case("close"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thread_pool::OverflowPolicy;
+    use std::io::{Read, Write};
+
+    fn start(server: Server) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(server);
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            server.handle_connection(stream).unwrap();
+        });
+
+        TcpStream::connect(address).unwrap()
+    }
+
+    fn hello_server() -> Server {
+        let mut router = Router::new();
+        router.get("/", |_: &HttpRequest| {
+            let mut response = HttpResponse::new(HttpStatus::Ok);
+            response.str_entity("hello", "text/plain");
+            response
+        });
+
+        Server::new(router)
+    }
+
+    fn read_response(stream: &mut TcpStream) -> String {
+        let mut buffer = [0u8; 1024];
+        let read = stream.read(&mut buffer).unwrap();
+        String::from_utf8_lossy(&buffer[..read]).into_owned()
+    }
+
+    #[test]
+    fn test_keep_alive_serves_many_requests() {
+        let mut stream = start(hello_server());
+
+        for _ in 0..3 {
+            stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+            let response = read_response(&mut stream);
+
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("Connection: keep-alive\r\n"));
+            assert!(response.ends_with("hello"));
+        }
+    }
+
+    #[test]
+    fn test_access_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("access.log");
+        let mut server = hello_server();
+        server.access_log(AccessLog::file(&path, LogFormat::Json).unwrap());
+        let mut stream = start(server);
+
+        stream
+            .write_all(b"GET /?x=1 HTTP/1.1\r\nUser-Agent: test\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        stream.read_to_end(&mut Vec::new()).unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(log.contains("\"method\":\"GET\",\"path\":\"/?x=1\""));
+        assert!(log.contains("\"status\":200,\"bytes\":5"));
+        assert!(log.contains("\"user_agent\":\"test\""));
+    }
+
+    #[test]
+    fn test_head_request_has_no_body() {
+        let mut stream = start(hello_server());
+
+        stream.write_all(b"HEAD / HTTP/1.1\r\n\r\n").unwrap();
+        let response = read_response(&mut stream);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 5\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(read_response(&mut stream).ends_with("hello"));
+    }
+
+    #[test]
+    fn test_connection_close() {
+        let mut stream = start(hello_server());
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let response = read_response(&mut stream);
+        assert!(response.contains("Connection: close\r\n"));
+
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_request_gets_error_response() {
+        for (raw, status_line) in [
+            (&b"NONSENSE\r\n\r\n"[..], "HTTP/1.1 400 Bad Request\r\n"),
+            (
+                &b"GET / HTTP/3.0\r\n\r\n"[..],
+                "HTTP/1.1 505 HTTP Version Not Supported\r\n",
+            ),
+        ] {
+            let mut stream = start(hello_server());
+            stream.write_all(raw).unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with(status_line));
+            assert!(response.contains("Connection: close\r\n"));
+        }
+    }
+
+    #[test]
+    fn test_parser_limits() {
+        let mut server = hello_server();
+        server.limits(ParserLimits {
+            max_body_size: 4,
+            header_timeout: Some(Duration::from_millis(100)),
+            ..ParserLimits::default()
+        });
+        let server = Arc::new(server);
+
+        for (raw, status_line) in [
+            (
+                &b"POST / HTTP/1.1\r\nContent-Length: 1000000000\r\n\r\n"[..],
+                "HTTP/1.1 413 Payload Too Large\r\n",
+            ),
+            (
+                &b"GET / HTTP/1.1\r\nHost: stalled"[..],
+                "HTTP/1.1 408 Request Timeout\r\n",
+            ),
+        ] {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            let server = Arc::clone(&server);
+            thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                server.handle_connection(stream).unwrap();
+            });
+
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(raw).unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            assert!(response.starts_with(status_line));
+        }
+    }
+
+    #[test]
+    fn test_header_deadline_stops_trickling_client() {
+        let mut server = hello_server();
+        server.limits(ParserLimits {
+            header_timeout: Some(Duration::from_millis(200)),
+            ..ParserLimits::default()
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (done, finished) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            done.send(server.handle_connection(stream).is_ok()).unwrap();
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\nX-Slow: ").unwrap();
+        let started = Instant::now();
+        let trickle = thread::spawn(move || {
+            for _ in 0..60 {
+                thread::sleep(Duration::from_millis(50));
+                if stream.write_all(b"a").is_err() {
+                    break;
+                }
+            }
+        });
+
+        assert!(finished.recv_timeout(Duration::from_secs(2)).unwrap());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        trickle.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_one_closes_by_default() {
+        let mut stream = start(hello_server());
+
+        stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        let response = read_response(&mut stream);
+        assert!(response.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_streamed_response_keeps_connection() {
+        let mut router = Router::new();
+        router.get("/stream", |_: &HttpRequest| {
+            let mut response = HttpResponse::new(HttpStatus::Ok);
+            response.chunked_entity(vec![b"hel".to_vec(), b"lo".to_vec()], "text/plain");
+            response
+        });
+        let mut stream = start(Server::new(router));
+
+        for _ in 0..2 {
+            stream.write_all(b"GET /stream HTTP/1.1\r\n\r\n").unwrap();
+            let response = read_response(&mut stream);
+
+            assert!(response.contains("Transfer-Encoding: chunked\r\n"));
+            assert!(response.ends_with("3\r\nhel\r\n2\r\nlo\r\n0\r\n\r\n"));
+        }
+    }
+
+    #[test]
+    fn test_serve_until_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let mut router = Router::new();
+        router.get("/slow", |_: &HttpRequest| {
+            thread::sleep(Duration::from_millis(200));
+            let mut response = HttpResponse::new(HttpStatus::Ok);
+            response.str_entity("done", "text/plain");
+            response
+        });
+        let server = Arc::new(Server::new(router));
+        let shutdown = server.shutdown_handle();
+
+        let serving = thread::spawn(move || server.serve(listener, ThreadPool::new(2)));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /slow HTTP/1.1\r\n\r\n").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        shutdown.shutdown();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(response.ends_with("done"));
+
+        serving.join().unwrap().unwrap();
+        assert!(TcpStream::connect(address).is_err());
+    }
+
+    #[test]
+    fn test_rejects_connections_when_pool_is_saturated() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(hello_server());
+        let shutdown = server.shutdown_handle();
+        let pool = ThreadPool::bounded(1, 1, OverflowPolicy::Reject);
+
+        let serving = thread::spawn(move || server.serve(listener, pool));
+
+        let busy = TcpStream::connect(address).unwrap();
+        thread::sleep(Duration::from_millis(150));
+        let queued = TcpStream::connect(address).unwrap();
+        thread::sleep(Duration::from_millis(150));
+
+        let mut rejected = TcpStream::connect(address).unwrap();
+        let mut response = String::new();
+        rejected.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+
+        shutdown.shutdown();
+        drop(busy);
+        drop(queued);
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_closes_idle_connections() {
+        let mut server = hello_server();
+        server.keep_alive_timeout(Duration::from_secs(60));
+        let shutdown = server.shutdown_handle();
+        let mut stream = start(server);
+
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(read_response(&mut stream).contains("Connection: keep-alive\r\n"));
+
+        shutdown.shutdown();
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_idle_timeout_closes_connection() {
+        let mut server = hello_server();
+        server.keep_alive_timeout(Duration::from_millis(50));
+        let mut stream = start(server);
+
+        let mut rest = Vec::new();
+        stream.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shutdown(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    pub fn register_signals(&self) -> io::Result<()> {
+        for signal in [SIGINT, SIGTERM] {
+            // A second signal while already shutting down exits immediately.
+            signal_hook::flag::register_conditional_shutdown(
+                signal,
+                1,
+                Arc::clone(&self.requested),
+            )?;
+            signal_hook::flag::register(signal, Arc::clone(&self.requested))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_state() {
+        let handle = ShutdownHandle::new();
+        let clone = handle.clone();
+        assert!(!clone.is_shutting_down());
+
+        handle.shutdown();
+        assert!(clone.is_shutting_down());
+    }
+}
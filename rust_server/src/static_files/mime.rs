@@ -0,0 +1,62 @@
+use std::path::Path;
+
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+pub fn from_path(path: &Path) -> &'static str {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| from_extension(&extension.to_ascii_lowercase()))
+        .unwrap_or(DEFAULT_MIME_TYPE)
+}
+
+pub fn from_extension(extension: &str) -> &'static str {
+    match extension {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "log" => "text/plain; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        _ => DEFAULT_MIME_TYPE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            "text/html; charset=utf-8",
+            from_path(Path::new("res/hello.html"))
+        );
+        assert_eq!("image/png", from_path(Path::new("logo.PNG")));
+        assert_eq!(DEFAULT_MIME_TYPE, from_path(Path::new("Makefile")));
+        assert_eq!(DEFAULT_MIME_TYPE, from_path(Path::new("data.unknown")));
+    }
+}
@@ -0,0 +1,635 @@
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    http::{
+        negotiate_encoding, parse_range_header, partial_content, percent_encode_segment,
+        range_not_satisfiable, ContentCoding, HttpMethod, HttpRequest, HttpResponse, HttpStatus,
+        RangeRequest, Validators,
+    },
+    router::Handler,
+};
+
+pub mod mime;
+
+pub struct StaticFiles {
+    prefix: Vec<String>,
+    root: PathBuf,
+    index_file: Option<String>,
+    directory_listing: bool,
+    precompressed: Vec<ContentCoding>,
+}
+
+impl StaticFiles {
+    pub fn new(prefix: &str, root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = fs::canonicalize(root)?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+
+        Ok(Self {
+            prefix: prefix
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            root,
+            index_file: Some(String::from("index.html")),
+            directory_listing: false,
+            precompressed: vec![ContentCoding::Brotli, ContentCoding::Gzip],
+        })
+    }
+
+    pub fn index_file(&mut self, name: Option<&str>) {
+        self.index_file = name.map(str::to_owned);
+    }
+
+    pub fn directory_listing(&mut self, enabled: bool) {
+        self.directory_listing = enabled;
+    }
+
+    pub fn precompressed(&mut self, codings: &[ContentCoding]) {
+        self.precompressed = codings.to_vec();
+    }
+
+    fn resolve(&self, segments: &[String]) -> Result<PathBuf, HttpStatus> {
+        let mut path = self.root.clone();
+
+        for segment in segments {
+            match segment.as_str() {
+                "" | "." => continue,
+                ".." => return Err(HttpStatus::Forbidden),
+                _ if segment.contains(['/', '\\', '\0']) => return Err(HttpStatus::Forbidden),
+                _ => path.push(segment),
+            }
+        }
+
+        self.contain(&path)
+    }
+
+    fn contain(&self, path: &Path) -> Result<PathBuf, HttpStatus> {
+        let canonical = fs::canonicalize(path).map_err(|e| io_error_status(&e))?;
+
+        if canonical.starts_with(&self.root) {
+            Ok(canonical)
+        } else {
+            Err(HttpStatus::Forbidden)
+        }
+    }
+
+    fn serve_path(&self, request: &HttpRequest, path: &Path) -> HttpResponse {
+        let content_type = mime::from_path(path);
+        if self.precompressed.is_empty() {
+            return serve_file_conditionally(request, path, content_type);
+        }
+
+        let variants = self
+            .precompressed
+            .iter()
+            .filter_map(|coding| {
+                let mut variant = path.as_os_str().to_owned();
+                variant.push(format!(".{}", coding.extension()));
+                let variant = self.contain(Path::new(&variant)).ok()?;
+                variant.is_file().then_some((*coding, variant))
+            })
+            .collect::<Vec<_>>();
+        let codings = variants
+            .iter()
+            .map(|(coding, _)| *coding)
+            .collect::<Vec<_>>();
+
+        let mut response = match negotiate_encoding(&request.headers, &codings) {
+            Some(coding) => {
+                let (_, variant) = variants.iter().find(|(c, _)| *c == coding).unwrap();
+                let mut response = serve_file_conditionally(request, variant, content_type);
+                response.headers.put("Content-Encoding", coding.token());
+                response
+            }
+            None => serve_file_conditionally(request, path, content_type),
+        };
+        if !variants.is_empty() {
+            response.headers.put_list_item("Vary", "Accept-Encoding");
+        }
+        response
+    }
+
+    fn serve_directory(&self, request: &HttpRequest, directory: &Path) -> HttpResponse {
+        if !request.uri.path.ends_with('/') {
+            let location = request
+                .uri
+                .segments
+                .iter()
+                .map(|segment| format!("/{}", percent_encode_segment(segment)))
+                .collect::<String>();
+
+            let mut response = HttpResponse::new(HttpStatus::MovedPermanently);
+            response.headers.put("Location", &format!("{location}/"));
+            return response;
+        }
+
+        if let Some(index_file) = &self.index_file {
+            if let Ok(index) = self.contain(&directory.join(index_file)) {
+                if index.is_file() {
+                    return self.serve_path(request, &index);
+                }
+            }
+        }
+
+        if !self.directory_listing {
+            return error_response(HttpStatus::Forbidden);
+        }
+
+        match render_listing(&request.uri.path, directory, directory == self.root) {
+            Ok(listing) => {
+                let mut response = HttpResponse::new(HttpStatus::Ok);
+                response.str_entity(&listing, mime::from_extension("html"));
+                response
+            }
+            Err(e) => error_response(io_error_status(&e)),
+        }
+    }
+}
+
+impl Handler for StaticFiles {
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let Some(relative_path) = request.uri.segments.strip_prefix(self.prefix.as_slice()) else {
+            return error_response(HttpStatus::NotFound);
+        };
+
+        let path = match self.resolve(relative_path) {
+            Ok(path) => path,
+            Err(status) => return error_response(status),
+        };
+
+        if path.is_dir() {
+            self.serve_directory(request, &path)
+        } else {
+            self.serve_path(request, &path)
+        }
+    }
+}
+
+pub fn serve_file(path: &Path) -> HttpResponse {
+    match open_file(path) {
+        Ok((file, metadata)) => file_response(file, &metadata, mime::from_path(path)),
+        Err(status) => error_response(status),
+    }
+}
+
+fn serve_file_conditionally(
+    request: &HttpRequest,
+    path: &Path,
+    content_type: &str,
+) -> HttpResponse {
+    let (file, metadata) = match open_file(path) {
+        Ok(opened) => opened,
+        Err(status) => return error_response(status),
+    };
+
+    let validators = Validators::from_metadata(&metadata);
+    if let Some(status) = validators.evaluate(request) {
+        let mut response = HttpResponse::new(status);
+        validators.apply(&mut response.headers);
+        return response;
+    }
+
+    let range = request
+        .headers
+        .get_first("Range")
+        .filter(|_| request.method == HttpMethod::GET && validators.if_range_matches(request))
+        .map(|range| parse_range_header(range, metadata.len()));
+
+    let mut response = match range {
+        Some(RangeRequest::Satisfiable(ranges)) => {
+            partial_content(file, &ranges, metadata.len(), content_type)
+        }
+        Some(RangeRequest::Unsatisfiable) => range_not_satisfiable(metadata.len()),
+        Some(RangeRequest::Ignored) | None => return file_response(file, &metadata, content_type),
+    };
+
+    validators.apply(&mut response.headers);
+    response.headers.put("Accept-Ranges", "bytes");
+    response
+}
+
+fn open_file(path: &Path) -> Result<(File, Metadata), HttpStatus> {
+    let file = File::open(path).map_err(|e| io_error_status(&e))?;
+    let metadata = file.metadata().map_err(|e| io_error_status(&e))?;
+
+    if metadata.is_file() {
+        Ok((file, metadata))
+    } else {
+        Err(HttpStatus::NotFound)
+    }
+}
+
+fn file_response(file: File, metadata: &Metadata, content_type: &str) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpStatus::Ok);
+    Validators::from_metadata(metadata).apply(&mut response.headers);
+    response.headers.put("Accept-Ranges", "bytes");
+    response.reader_entity(file, Some(metadata.len()), content_type);
+    response
+}
+
+fn render_listing(request_path: &str, directory: &Path, is_root: bool) -> io::Result<String> {
+    let mut entries = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            (name, is_dir)
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    let title = escape_html(request_path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n\
+         <body>\n<h1>Index of {title}</h1>\n<ul>\n"
+    );
+
+    if !is_root {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for (name, is_dir) in entries {
+        let suffix = if is_dir { "/" } else { "" };
+        // `:` is encoded too so a name like `a:b` isn't read as a URL scheme.
+        let href = percent_encode_segment(&name).replace(':', "%3A");
+        html.push_str(&format!(
+            "<li><a href=\"{}{suffix}\">{}{suffix}</a></li>\n",
+            escape_html(&href),
+            escape_html(&name)
+        ));
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+
+    Ok(html)
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn io_error_status(error: &io::Error) -> HttpStatus {
+    match error.kind() {
+        ErrorKind::NotFound => HttpStatus::NotFound,
+        ErrorKind::PermissionDenied => HttpStatus::Forbidden,
+        _ => HttpStatus::InternalServerError,
+    }
+}
+
+fn error_response(status: HttpStatus) -> HttpResponse {
+    let reason_phrase = status.reason_phrase().to_owned();
+    let mut response = HttpResponse::new(status);
+    response.str_entity(&reason_phrase, "text/plain; charset=utf-8");
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    struct TestDir(tempfile::TempDir);
+
+    impl TestDir {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path();
+            fs::create_dir_all(path.join("public/docs")).unwrap();
+            fs::write(path.join("public/hello.txt"), "hello").unwrap();
+            fs::write(
+                path.join("public/logo.png"),
+                [0x89, b'P', b'N', b'G', 0, 0xff],
+            )
+            .unwrap();
+            fs::write(path.join("public/docs/a<b>.md"), "# doc").unwrap();
+            fs::write(path.join("secret.txt"), "secret").unwrap();
+            Self(dir)
+        }
+
+        fn path(&self) -> &Path {
+            self.0.path()
+        }
+
+        fn files(&self) -> StaticFiles {
+            StaticFiles::new("/static", self.path().join("public")).unwrap()
+        }
+    }
+
+    fn get(files: &StaticFiles, path: &str) -> HttpResponse {
+        get_with(files, path, &[])
+    }
+
+    fn get_with(files: &StaticFiles, path: &str, headers: &[(&str, &str)]) -> HttpResponse {
+        let mut raw = format!("GET {path} HTTP/1.1\r\n");
+        for (key, value) in headers {
+            raw.push_str(&format!("{key}: {value}\r\n"));
+        }
+        raw.push_str("\r\n");
+
+        files.handle(&HttpRequest::build(&mut raw.as_bytes()).unwrap())
+    }
+
+    fn body(response: HttpResponse) -> Vec<u8> {
+        let mut output = Vec::new();
+        match response.entity {
+            Some(crate::http::HttpEntity::Reader { mut reader, .. }) => {
+                reader.read_to_end(&mut output).unwrap();
+            }
+            Some(entity) => output.extend_from_slice(entity.as_bytes().unwrap()),
+            None => {}
+        }
+        output
+    }
+
+    #[test]
+    fn test_serves_text_and_binary_files() {
+        let dir = TestDir::new();
+        let files = dir.files();
+
+        let response = get(&files, "/static/hello.txt");
+        assert_eq!(HttpStatus::Ok, response.status);
+        assert_eq!(
+            Some("text/plain; charset=utf-8"),
+            response.headers.get_first("Content-Type")
+        );
+        assert_eq!(b"hello".to_vec(), body(response));
+
+        assert_eq!(HttpStatus::Ok, get(&files, "/static/%68ello.txt").status);
+        assert_eq!(HttpStatus::Ok, get(&files, "/st%61tic/hello.txt").status);
+        assert_eq!(HttpStatus::Ok, get(&files, "/x/../static/hello.txt").status);
+
+        let response = get(&files, "/static/logo.png?v=2");
+        assert_eq!(
+            Some("image/png"),
+            response.headers.get_first("Content-Type")
+        );
+        assert_eq!(Some("6"), response.headers.get_first("Content-Length"));
+        assert_eq!(vec![0x89, b'P', b'N', b'G', 0, 0xff], body(response));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let dir = TestDir::new();
+        let files = dir.files();
+
+        assert_eq!(HttpStatus::NotFound, get(&files, "/static/nope.txt").status);
+        assert_eq!(HttpStatus::NotFound, get(&files, "/other/hello.txt").status);
+        assert_eq!(HttpStatus::NotFound, get(&files, "/statichello.txt").status);
+    }
+
+    #[test]
+    fn test_refuses_traversal() {
+        let dir = TestDir::new();
+        let files = dir.files();
+
+        assert_eq!(
+            HttpStatus::NotFound,
+            get(&files, "/static/../secret.txt").status
+        );
+        assert_eq!(
+            HttpStatus::NotFound,
+            get(&files, "/static/docs/../../secret.txt").status
+        );
+        assert_eq!(
+            HttpStatus::Forbidden,
+            get(&files, "/static/..\\secret.txt").status
+        );
+        assert_eq!(
+            HttpStatus::NotFound,
+            get(&files, "/static/%2e%2e/secret.txt").status
+        );
+        assert_eq!(
+            HttpStatus::Forbidden,
+            get(&files, "/static/..%2Fsecret.txt").status
+        );
+        assert_eq!(
+            HttpStatus::Forbidden,
+            get(&files, "/static/..%5csecret.txt").status
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_refuses_symlink_escape() {
+        let dir = TestDir::new();
+        std::os::unix::fs::symlink(
+            dir.path().join("secret.txt"),
+            dir.path().join("public/link.txt"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(
+            dir.path().join("public/hello.txt"),
+            dir.path().join("public/ok.txt"),
+        )
+        .unwrap();
+        let files = dir.files();
+
+        assert_eq!(
+            HttpStatus::Forbidden,
+            get(&files, "/static/link.txt").status
+        );
+        assert_eq!(HttpStatus::Ok, get(&files, "/static/ok.txt").status);
+    }
+
+    #[test]
+    fn test_directories() {
+        let dir = TestDir::new();
+        let mut files = dir.files();
+
+        fs::write(dir.path().join("public/docs/x:y #?.txt"), "").unwrap();
+        fs::create_dir(dir.path().join("public/docs/a&b")).unwrap();
+
+        let response = get(&files, "/static/docs");
+        assert_eq!(HttpStatus::MovedPermanently, response.status);
+        assert_eq!(
+            Some("/static/docs/"),
+            response.headers.get_first("Location")
+        );
+
+        assert_eq!(HttpStatus::Forbidden, get(&files, "/static/docs/").status);
+
+        files.directory_listing(true);
+        let response = get(&files, "/static/docs/");
+        assert_eq!(HttpStatus::Ok, response.status);
+        let listing = String::from_utf8(body(response)).unwrap();
+        assert!(listing.contains("<a href=\"a%3Cb%3E.md\">a&lt;b&gt;.md</a>"));
+        assert!(listing.contains("<a href=\"x%3Ay%20%23%3F.txt\">x:y #?.txt</a>"));
+        assert!(listing.contains("<a href=\"a&amp;b/\">a&amp;b/</a>"));
+        assert!(listing.contains("<a href=\"../\">"));
+
+        fs::write(dir.path().join("public/docs/index.html"), "<h1>Docs</h1>").unwrap();
+        let response = get(&files, "/static/docs/");
+        assert_eq!(b"<h1>Docs</h1>".to_vec(), body(response));
+    }
+
+    #[test]
+    fn test_conditional_get() {
+        let dir = TestDir::new();
+        let files = dir.files();
+
+        let response = get(&files, "/static/hello.txt");
+        let etag = response.headers.get_first("ETag").unwrap().to_owned();
+        let last_modified = response
+            .headers
+            .get_first("Last-Modified")
+            .unwrap()
+            .to_owned();
+
+        let response = get_with(&files, "/static/hello.txt", &[("If-None-Match", &etag)]);
+        assert_eq!(HttpStatus::NotModified, response.status);
+        assert_eq!(Some(etag.as_str()), response.headers.get_first("ETag"));
+        assert!(response.entity.is_none());
+
+        let response = get_with(
+            &files,
+            "/static/hello.txt",
+            &[("If-Modified-Since", &last_modified)],
+        );
+        assert_eq!(HttpStatus::NotModified, response.status);
+
+        let response = get_with(&files, "/static/hello.txt", &[("If-Match", "\"stale\"")]);
+        assert_eq!(HttpStatus::PreconditionFailed, response.status);
+
+        let response = get_with(
+            &files,
+            "/static/hello.txt",
+            &[("If-None-Match", "\"stale\"")],
+        );
+        assert_eq!(HttpStatus::Ok, response.status);
+        assert_eq!(b"hello".to_vec(), body(response));
+    }
+
+    #[test]
+    fn test_range_requests() {
+        let dir = TestDir::new();
+        let files = dir.files();
+
+        let response = get(&files, "/static/hello.txt");
+        assert_eq!(Some("bytes"), response.headers.get_first("Accept-Ranges"));
+        let etag = response.headers.get_first("ETag").unwrap().to_owned();
+
+        let response = get_with(&files, "/static/hello.txt", &[("Range", "bytes=1-3")]);
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        assert_eq!(
+            Some("bytes 1-3/5"),
+            response.headers.get_first("Content-Range")
+        );
+        assert_eq!(b"ell".to_vec(), body(response));
+
+        let response = get_with(&files, "/static/hello.txt", &[("Range", "bytes=0-0,-1")]);
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        let content_type = response.headers.get_first("Content-Type").unwrap();
+        assert!(content_type.starts_with("multipart/byteranges; boundary="));
+
+        let response = get_with(&files, "/static/hello.txt", &[("Range", "bytes=0-1,1-2")]);
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        assert_eq!(
+            Some("bytes 0-2/5"),
+            response.headers.get_first("Content-Range")
+        );
+        assert_eq!(b"hel".to_vec(), body(response));
+
+        let response = get_with(&files, "/static/hello.txt", &[("Range", "bytes=0-,0-,0-")]);
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        assert_eq!(
+            Some("bytes 0-4/5"),
+            response.headers.get_first("Content-Range")
+        );
+        assert_eq!(b"hello".to_vec(), body(response));
+
+        let response = get_with(&files, "/static/hello.txt", &[("Range", "bytes=10-")]);
+        assert_eq!(HttpStatus::RangeNotSatisfiable, response.status);
+        assert_eq!(
+            Some("bytes */5"),
+            response.headers.get_first("Content-Range")
+        );
+
+        let response = get_with(
+            &files,
+            "/static/hello.txt",
+            &[("Range", "bytes=-2"), ("If-Range", &etag)],
+        );
+        assert_eq!(HttpStatus::PartialContent, response.status);
+        assert_eq!(b"lo".to_vec(), body(response));
+
+        let response = get_with(
+            &files,
+            "/static/hello.txt",
+            &[("Range", "bytes=-2"), ("If-Range", "\"stale\"")],
+        );
+        assert_eq!(HttpStatus::Ok, response.status);
+        assert_eq!(b"hello".to_vec(), body(response));
+    }
+
+    #[test]
+    fn test_precompressed_variants() {
+        let dir = TestDir::new();
+        fs::write(dir.path().join("public/hello.txt.gz"), "gzipped").unwrap();
+        fs::write(dir.path().join("public/hello.txt.br"), "brotli").unwrap();
+        let mut files = dir.files();
+
+        let response = get_with(
+            &files,
+            "/static/hello.txt",
+            &[("Accept-Encoding", "gzip, br")],
+        );
+        assert_eq!(Some("br"), response.headers.get_first("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding"), response.headers.get_first("Vary"));
+        assert_eq!(
+            Some("text/plain; charset=utf-8"),
+            response.headers.get_first("Content-Type")
+        );
+        assert_eq!(b"brotli".to_vec(), body(response));
+
+        let response = get_with(&files, "/static/hello.txt", &[("Accept-Encoding", "gzip")]);
+        assert_eq!(Some("gzip"), response.headers.get_first("Content-Encoding"));
+        assert_eq!(b"gzipped".to_vec(), body(response));
+
+        let response = get(&files, "/static/hello.txt");
+        assert_eq!(None, response.headers.get_first("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding"), response.headers.get_first("Vary"));
+        assert_eq!(b"hello".to_vec(), body(response));
+
+        files.precompressed(&[]);
+        let response = get_with(&files, "/static/hello.txt", &[("Accept-Encoding", "br")]);
+        assert_eq!(None, response.headers.get_first("Content-Encoding"));
+        assert_eq!(None, response.headers.get_first("Vary"));
+    }
+
+    #[test]
+    fn test_serve_file() {
+        let dir = TestDir::new();
+
+        assert_eq!(
+            HttpStatus::Ok,
+            serve_file(&dir.path().join("secret.txt")).status
+        );
+        assert_eq!(
+            HttpStatus::NotFound,
+            serve_file(&dir.path().join("nope")).status
+        );
+        assert_eq!(
+            HttpStatus::NotFound,
+            serve_file(&dir.path().join("public")).status
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    any::Any,
+    io,
+    sync::{Arc, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use super::{OverflowPolicy, PanicHandler, Shared, ThreadPool, WorkerConfig};
+
+const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct ThreadPoolBuilder {
+    name_prefix: String,
+    stack_size: Option<usize>,
+    min_workers: usize,
+    max_workers: usize,
+    keep_alive: Duration,
+    queue_capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    panic_handler: Option<Arc<PanicHandler>>,
+}
+
+impl ThreadPoolBuilder {
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism().map_or(4, |n| n.get());
+
+        Self {
+            name_prefix: String::from("worker"),
+            stack_size: None,
+            min_workers: workers,
+            max_workers: workers,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+            queue_capacity: None,
+            overflow_policy: OverflowPolicy::Block,
+            panic_handler: None,
+        }
+    }
+
+    pub fn name_prefix(mut self, name_prefix: &str) -> Self {
+        self.name_prefix = name_prefix.to_owned();
+        self
+    }
+
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.min_workers = workers;
+        self.max_workers = workers;
+        self
+    }
+
+    pub fn min_workers(mut self, min_workers: usize) -> Self {
+        self.min_workers = min_workers;
+        self
+    }
+
+    pub fn max_workers(mut self, max_workers: usize) -> Self {
+        self.max_workers = max_workers;
+        self
+    }
+
+    pub fn keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = Some(queue_capacity);
+        self
+    }
+
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    pub fn panic_handler<H>(mut self, handler: H) -> Self
+    where
+        H: Fn(usize, &(dyn Any + Send)) + Send + Sync + 'static,
+    {
+        self.panic_handler = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn build(self) -> io::Result<ThreadPool> {
+        if self.max_workers == 0 {
+            return Err(invalid_input("max_workers must be greater than zero"));
+        }
+        if self.min_workers > self.max_workers {
+            return Err(invalid_input("min_workers must not exceed max_workers"));
+        }
+        if self.queue_capacity == Some(0) {
+            return Err(invalid_input("queue_capacity must be greater than zero"));
+        }
+
+        let config = WorkerConfig {
+            name_prefix: self.name_prefix,
+            stack_size: self.stack_size,
+            min_workers: self.min_workers,
+            max_workers: self.max_workers,
+            keep_alive: self.keep_alive,
+            panic_handler: self.panic_handler,
+        };
+
+        let pool = ThreadPool {
+            shared: Arc::new(Shared::new(
+                config,
+                self.queue_capacity,
+                self.overflow_policy,
+            )),
+            scheduler: OnceLock::new(),
+        };
+
+        for _ in 0..self.min_workers {
+            pool.shared.spawn_worker()?;
+        }
+
+        Ok(pool)
+    }
+}
+
+impl Default for ThreadPoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,69 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::mpsc;
+use std::thread;
+
+pub struct JobHandle<T> {
+    receiver: mpsc::Receiver<thread::Result<T>>,
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    Panicked(Box<dyn Any + Send + 'static>),
+    Cancelled,
+}
+
+impl<T> JobHandle<T> {
+    pub(super) fn new(receiver: mpsc::Receiver<thread::Result<T>>) -> Self {
+        Self { receiver }
+    }
+
+    pub fn join(self) -> Result<T, JobError> {
+        match self.receiver.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(payload)) => Err(JobError::Panicked(payload)),
+            Err(_) => Err(JobError::Cancelled),
+        }
+    }
+}
+
+pub fn join_all<T, I>(handles: I) -> Vec<Result<T, JobError>>
+where
+    I: IntoIterator<Item = JobHandle<T>>,
+{
+    handles.into_iter().map(JobHandle::join).collect()
+}
+
+impl JobError {
+    pub fn panic_message(&self) -> Option<&str> {
+        match self {
+            Self::Panicked(payload) => payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str)),
+            Self::Cancelled => None,
+        }
+    }
+
+    pub fn into_panic(self) -> Option<Box<dyn Any + Send + 'static>> {
+        match self {
+            Self::Panicked(payload) => Some(payload),
+            Self::Cancelled => None,
+        }
+    }
+}
+
+impl Display for JobError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Panicked(_) => match self.panic_message() {
+                Some(message) => write!(f, "Job panicked: {message}"),
+                None => f.write_str("Job panicked"),
+            },
+            Self::Cancelled => f.write_str("Job was dropped before it ran"),
+        }
+    }
+}
+
+impl Error for JobError {}
@@ -1,18 +1,92 @@
-use std::panic::UnwindSafe;
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::{
-    panic,
-    sync::{mpsc, Arc, Mutex},
-    thread::{self, JoinHandle},
+    io, panic,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex, OnceLock, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
+use crossbeam_deque::{Injector, Stealer};
+
+mod builder;
+mod handle;
+mod scheduler;
+mod scope;
+mod worker;
+
+pub use builder::ThreadPoolBuilder;
+pub use handle::{join_all, JobError, JobHandle};
+pub use scheduler::ScheduledHandle;
+use scheduler::Scheduler;
+pub use scope::Scope;
+use worker::Worker;
+
 pub struct ThreadPool {
-    workers: Vec<Worker>,
-    sender: Option<mpsc::Sender<Job>>,
+    shared: Arc<Shared>,
+    scheduler: OnceLock<Scheduler>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    Block,
+    Reject,
+    CallerRuns,
 }
 
-struct Worker {
-    id: usize,
-    thread: Option<JoinHandle<()>>,
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecuteError {
+    QueueFull,
+    ShutDown,
+    Spawn(io::ErrorKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerStats {
+    pub id: usize,
+    pub jobs_run: u64,
+    pub panics: u64,
+}
+
+pub type PanicHandler = dyn Fn(usize, &(dyn Any + Send)) + Send + Sync;
+
+struct WorkerConfig {
+    name_prefix: String,
+    stack_size: Option<usize>,
+    min_workers: usize,
+    max_workers: usize,
+    keep_alive: Duration,
+    panic_handler: Option<Arc<PanicHandler>>,
+}
+
+struct Shared {
+    injector: Injector<Job>,
+    stealers: RwLock<Vec<(usize, Stealer<Job>)>>,
+    stealers_generation: AtomicUsize,
+    config: WorkerConfig,
+    capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    workers: Mutex<Vec<Worker>>,
+    sleep_lock: Mutex<()>,
+    job_available: Condvar,
+    space_available: Condvar,
+    shutting_down: AtomicBool,
+    next_id: AtomicUsize,
+    live: AtomicUsize,
+    idle: AtomicUsize,
+    sleeping: AtomicUsize,
+    blocked: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+enum Wakeup {
+    Notified,
+    TimedOut,
 }
 
 type Job = Box<dyn FnOnce() + Send + UnwindSafe + 'static>;
@@ -20,69 +94,908 @@ type Job = Box<dyn FnOnce() + Send + UnwindSafe + 'static>;
 impl ThreadPool {
     pub fn new(size: usize) -> Self {
         assert!(size > 0);
-        let mut workers = Vec::with_capacity(size);
+        ThreadPoolBuilder::new().workers(size).build().unwrap()
+    }
+
+    pub fn bounded(size: usize, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        assert!(size > 0 && capacity > 0);
+        ThreadPoolBuilder::new()
+            .workers(size)
+            .queue_capacity(capacity)
+            .overflow_policy(overflow_policy)
+            .build()
+            .unwrap()
+    }
+
+    pub fn execute<F>(&self, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + UnwindSafe + 'static,
+    {
+        self.execute_job(Box::new(f))
+    }
+
+    fn execute_job(&self, job: Job) -> Result<(), ExecuteError> {
+        self.shared.execute(job)
+    }
+
+    pub fn submit<F, T>(&self, f: F) -> Result<JobHandle<T>, ExecuteError>
+    where
+        F: FnOnce() -> T + Send + UnwindSafe + 'static,
+        T: Send + 'static,
+    {
         let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        self.execute(move || {
+            let _ = sender.send(panic::catch_unwind(f));
+        })?;
+
+        Ok(JobHandle::new(receiver))
+    }
+
+    pub fn schedule<F>(&self, delay: Duration, f: F) -> Result<ScheduledHandle, ExecuteError>
+    where
+        F: FnOnce() + Send + UnwindSafe + 'static,
+    {
+        self.scheduler()?.schedule_once(delay, Box::new(f))
+    }
 
-        for i in 0..size {
-            workers.push(Worker::new(i, Arc::clone(&receiver)));
+    pub fn schedule_interval<F>(
+        &self,
+        interval: Duration,
+        f: F,
+    ) -> Result<ScheduledHandle, ExecuteError>
+    where
+        F: Fn() + Send + Sync + RefUnwindSafe + 'static,
+    {
+        self.scheduler()?.schedule_repeating(interval, Arc::new(f))
+    }
+
+    fn scheduler(&self) -> Result<&Scheduler, ExecuteError> {
+        if self.shared.is_shutting_down() {
+            return Err(ExecuteError::ShutDown);
+        }
+        if let Some(scheduler) = self.scheduler.get() {
+            return Ok(scheduler);
         }
 
-        Self {
-            workers,
-            sender: Some(sender),
+        let scheduler =
+            Scheduler::new(Arc::clone(&self.shared)).map_err(|e| ExecuteError::Spawn(e.kind()))?;
+        // Another caller may have started one first; keep theirs.
+        if let Err(scheduler) = self.scheduler.set(scheduler) {
+            scheduler.shut_down();
+            scheduler.join();
         }
+        Ok(self.scheduler.get().unwrap())
     }
 
-    pub fn execute<F>(&self, f: F)
+    fn stop_scheduler(&self) {
+        if let Some(scheduler) = self.scheduler.get() {
+            scheduler.shut_down();
+        }
+        self.shared.shut_down();
+        if let Some(scheduler) = self.scheduler.get() {
+            scheduler.join();
+        }
+    }
+
+    pub fn scope<'env, F, T>(&self, f: F) -> T
     where
-        F: FnOnce() + Send + UnwindSafe + 'static,
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
     {
-        let job = Box::new(f);
+        let scope = Scope::new(self);
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| f(&scope)));
+        scope.wait(&self.shared);
+
+        match (result, scope.take_panic()) {
+            (Err(payload), _) | (Ok(_), Some(payload)) => panic::resume_unwind(payload),
+            (Ok(value), None) => value,
+        }
+    }
+
+    pub fn queued_jobs(&self) -> usize {
+        self.shared.queued.load(Ordering::SeqCst)
+    }
 
-        if let Some(sender) = &self.sender {
-            sender.send(job).unwrap();
+    pub fn capacity(&self) -> Option<usize> {
+        self.shared.capacity
+    }
+
+    pub fn size(&self) -> usize {
+        self.shared.live.load(Ordering::SeqCst)
+    }
+
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        let workers = self.shared.workers.lock().unwrap();
+        workers
+            .iter()
+            .filter(|worker| worker.is_running() && !worker.is_finished())
+            .map(Worker::stats)
+            .collect()
+    }
+
+    pub fn shutdown_timeout(&mut self, timeout: Duration) -> usize {
+        self.stop_scheduler();
+        let deadline = Instant::now() + timeout;
+        let mut workers = self.shared.take_workers();
+
+        loop {
+            for worker in &mut workers {
+                if worker.is_finished() {
+                    worker.join();
+                }
+            }
+
+            let running = workers.iter().any(Worker::is_running);
+            if !running || Instant::now() >= deadline {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let mut abandoned = 0;
+        for worker in &mut workers {
+            if worker.detach() {
+                eprintln!("Worker {} did not finish in time; abandoning.", worker.id);
+                abandoned += 1;
+            }
+        }
+
+        abandoned
+    }
+}
+
+impl Shared {
+    fn new(config: WorkerConfig, capacity: Option<usize>, overflow_policy: OverflowPolicy) -> Self {
+        Self {
+            injector: Injector::new(),
+            stealers: RwLock::new(Vec::with_capacity(config.max_workers)),
+            stealers_generation: AtomicUsize::new(0),
+            workers: Mutex::new(Vec::with_capacity(config.max_workers)),
+            live: AtomicUsize::new(config.min_workers),
+            config,
+            capacity,
+            overflow_policy,
+            sleep_lock: Mutex::new(()),
+            job_available: Condvar::new(),
+            space_available: Condvar::new(),
+            shutting_down: AtomicBool::new(false),
+            next_id: AtomicUsize::new(0),
+            idle: AtomicUsize::new(0),
+            sleeping: AtomicUsize::new(0),
+            blocked: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    fn execute(self: &Arc<Self>, job: Job) -> Result<(), ExecuteError> {
+        if self.is_shutting_down() {
+            return Err(ExecuteError::ShutDown);
+        }
+
+        match self.capacity {
+            None => {
+                self.queued.fetch_add(1, Ordering::SeqCst);
+            }
+            Some(capacity) if !self.try_reserve(capacity) => match self.overflow_policy {
+                OverflowPolicy::Block => {
+                    self.grow_if_needed();
+                    self.reserve_blocking(capacity)?;
+                }
+                OverflowPolicy::Reject => return Err(ExecuteError::QueueFull),
+                OverflowPolicy::CallerRuns => {
+                    if panic::catch_unwind(job).is_err() {
+                        eprintln!("Job on caller thread panicked!");
+                    }
+                    return Ok(());
+                }
+            },
+            Some(_) => {}
+        }
+
+        self.push(job);
+        Ok(())
+    }
+
+    fn spawn_worker(self: &Arc<Self>) -> io::Result<()> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let worker = Worker::new(id, Arc::clone(self))?;
+
+        let mut workers = self.workers.lock().unwrap();
+        workers.retain_mut(|worker| {
+            if worker.is_finished() {
+                worker.join();
+            }
+            worker.is_running()
+        });
+        workers.push(worker);
+
+        Ok(())
+    }
+
+    fn grow_if_needed(self: &Arc<Self>) {
+        if self.queued.load(Ordering::SeqCst) <= self.idle.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let max_workers = self.config.max_workers;
+        let reserved = self
+            .live
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live < max_workers).then_some(live + 1)
+            })
+            .is_ok();
+
+        if reserved {
+            if let Err(e) = self.spawn_worker() {
+                self.live.fetch_sub(1, Ordering::SeqCst);
+                eprintln!("Failed to spawn worker: {e}");
+            }
+        }
+    }
+
+    fn retire_idle_worker(&self) -> bool {
+        let min_workers = self.config.min_workers;
+        self.live
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |live| {
+                (live > min_workers).then_some(live - 1)
+            })
+            .is_ok()
+    }
+
+    fn try_reserve(&self, capacity: usize) -> bool {
+        self.queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < capacity).then_some(queued + 1)
+            })
+            .is_ok()
+    }
+
+    fn reserve_blocking(&self, capacity: usize) -> Result<(), ExecuteError> {
+        let mut guard = self.sleep_lock.lock().unwrap();
+        self.blocked.fetch_add(1, Ordering::SeqCst);
+        let result = loop {
+            if self.is_shutting_down() {
+                break Err(ExecuteError::ShutDown);
+            }
+            if self.try_reserve(capacity) {
+                break Ok(());
+            }
+            guard = self.space_available.wait(guard).unwrap();
         };
+        self.blocked.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    fn push(self: &Arc<Self>, job: Job) {
+        if let Some(job) = worker::push_local(self, job) {
+            self.injector.push(job);
+        }
+        self.wake_worker();
+    }
+
+    // The counters here and in `wait_for_job` pair a write with a read of the
+    // other side's counter, so they need SeqCst to avoid a missed wakeup.
+    fn wake_worker(self: &Arc<Self>) {
+        let idle = self.idle.load(Ordering::SeqCst);
+        let sleeping = self.sleeping.load(Ordering::SeqCst);
+
+        // A worker that is awake and looking for work will find the job.
+        if idle > sleeping {
+            return;
+        }
+        if sleeping > 0 {
+            let _guard = self.sleep_lock.lock().unwrap();
+            self.job_available.notify_one();
+        } else {
+            self.grow_if_needed();
+        }
+    }
+
+    fn job_taken(&self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+
+        if self.blocked.load(Ordering::SeqCst) > 0 {
+            let _guard = self.sleep_lock.lock().unwrap();
+            self.space_available.notify_one();
+        }
+    }
+
+    fn wait_for_job(&self) -> Wakeup {
+        let guard = self.sleep_lock.lock().unwrap();
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+
+        // Rechecked under the lock so a push between the search and the wait is not missed.
+        if self.queued.load(Ordering::SeqCst) > 0 || self.is_shutting_down() {
+            self.sleeping.fetch_sub(1, Ordering::SeqCst);
+            // The job may be counted but not pushed yet; give its producer a
+            // chance to finish instead of spinning on the lock.
+            drop(guard);
+            thread::yield_now();
+            return Wakeup::Notified;
+        }
+
+        let (_guard, result) = self
+            .job_available
+            .wait_timeout(guard, self.config.keep_alive)
+            .unwrap();
+        self.sleeping.fetch_sub(1, Ordering::SeqCst);
+
+        if result.timed_out() {
+            Wakeup::TimedOut
+        } else {
+            Wakeup::Notified
+        }
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    fn shut_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+
+        let _guard = self.sleep_lock.lock().unwrap();
+        self.job_available.notify_all();
+        self.space_available.notify_all();
+    }
+
+    fn update_stealers(&self, update: impl FnOnce(&mut Vec<(usize, Stealer<Job>)>)) {
+        let mut stealers = self.stealers.write().unwrap();
+        update(&mut stealers);
+        self.stealers_generation.fetch_add(1, Ordering::Release);
+    }
+
+    fn take_workers(&self) -> Vec<Worker> {
+        std::mem::take(&mut *self.workers.lock().unwrap())
+    }
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QueueFull => f.write_str("Thread pool queue is full"),
+            Self::ShutDown => f.write_str("Thread pool is shut down"),
+            Self::Spawn(kind) => write!(f, "Thread pool failed to spawn a thread: {kind}"),
+        }
     }
 }
 
+impl Error for ExecuteError {}
+
 impl Drop for ThreadPool {
     fn drop(&mut self) {
-        drop(self.sender.take());
-
-        for worker in &mut self.workers {
-            println!("Shutting down worker {}", worker.id);
+        self.stop_scheduler();
 
-            if let Some(thread) = worker.thread.take() {
-                thread.join().unwrap();
-            }
+        for mut worker in self.shared.take_workers() {
+            worker.join();
         }
     }
 }
 
-impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Self {
-        let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv();
-
-            match message {
-                Ok(job) => {
-                    println!("Worker {id} got a job; executing.");
-                    let job_result = panic::catch_unwind(job);
-                    if job_result.is_err() {
-                        eprintln!("Job on Worker {id} panicked!")
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Receiver;
+
+    fn occupy_worker(pool: &ThreadPool) -> mpsc::Sender<()> {
+        let (started_sender, started) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+
+        pool.execute(move || {
+            started_sender.send(()).unwrap();
+            let _ = released.lock().unwrap().recv();
+        })
+        .unwrap();
+        started.recv().unwrap();
+
+        release
+    }
+
+    fn wait_for(receiver: &Receiver<thread::ThreadId>) -> thread::ThreadId {
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn test_execute() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let pool = ThreadPool::new(3);
+
+        for _ in 0..10 {
+            let counter = Arc::clone(&counter);
+            pool.execute(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        }
+        drop(pool);
+
+        assert_eq!(10, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_panicking_job_does_not_kill_worker() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let pool = ThreadPool::new(1);
+
+        pool.execute(|| panic!("boom")).unwrap();
+        let job_counter = Arc::clone(&counter);
+        pool.execute(move || {
+            job_counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+        drop(pool);
+
+        assert_eq!(1, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_shutdown_timeout_waits_for_jobs() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let mut pool = ThreadPool::new(2);
+
+        for _ in 0..2 {
+            let counter = Arc::clone(&counter);
+            pool.execute(move || {
+                thread::sleep(Duration::from_millis(50));
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        }
+
+        assert_eq!(0, pool.shutdown_timeout(Duration::from_secs(5)));
+        assert_eq!(2, counter.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_shutdown_timeout_abandons_slow_jobs() {
+        let mut pool = ThreadPool::new(1);
+        pool.execute(|| thread::sleep(Duration::from_secs(2)))
+            .unwrap();
+        thread::sleep(Duration::from_millis(20));
+
+        let started = Instant::now();
+        assert_eq!(1, pool.shutdown_timeout(Duration::from_millis(50)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_reject_when_queue_is_full() {
+        let pool = ThreadPool::bounded(1, 1, OverflowPolicy::Reject);
+        let release = occupy_worker(&pool);
+
+        pool.execute(|| {}).unwrap();
+        assert_eq!(1, pool.queued_jobs());
+        assert_eq!(Some(1), pool.capacity());
+
+        assert_eq!(Err(ExecuteError::QueueFull), pool.execute(|| {}));
+        assert_eq!(1, pool.queued_jobs());
+
+        release.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_caller_runs_when_queue_is_full() {
+        let pool = ThreadPool::bounded(1, 1, OverflowPolicy::CallerRuns);
+        let release = occupy_worker(&pool);
+        pool.execute(|| {}).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(move || sender.send(thread::current().id()).unwrap())
+            .unwrap();
+        assert_eq!(thread::current().id(), wait_for(&receiver));
+
+        release.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_block_when_queue_is_full() {
+        let pool = ThreadPool::bounded(1, 1, OverflowPolicy::Block);
+        let release = occupy_worker(&pool);
+        pool.execute(|| {}).unwrap();
+
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            release.send(()).unwrap();
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let started = Instant::now();
+        pool.execute(move || sender.send(thread::current().id()).unwrap())
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_ne!(thread::current().id(), wait_for(&receiver));
+
+        releaser.join().unwrap();
+    }
+
+    #[test]
+    fn test_execute_after_shutdown() {
+        let mut pool = ThreadPool::new(1);
+        pool.shutdown_timeout(Duration::from_secs(1));
+
+        assert_eq!(Err(ExecuteError::ShutDown), pool.execute(|| {}));
+    }
+
+    #[test]
+    fn test_worker_thread_names() {
+        let pool = ThreadPoolBuilder::new()
+            .name_prefix("test-pool")
+            .workers(1)
+            .stack_size(256 * 1024)
+            .build()
+            .unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(move || {
+            sender
+                .send(thread::current().name().map(str::to_owned))
+                .unwrap();
+        })
+        .unwrap();
+
+        assert_eq!(Some(String::from("test-pool-0")), receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_grows_under_load_and_shrinks_when_idle() {
+        let pool = ThreadPoolBuilder::new()
+            .min_workers(1)
+            .max_workers(3)
+            .keep_alive(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        assert_eq!(1, pool.size());
+
+        let releases = (0..3).map(|_| occupy_worker(&pool)).collect::<Vec<_>>();
+        assert_eq!(3, pool.size());
+
+        pool.execute(|| {}).unwrap();
+        assert_eq!(3, pool.size());
+
+        for release in releases {
+            release.send(()).unwrap();
+        }
+
+        let started = Instant::now();
+        while pool.size() > 1 && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(1, pool.size());
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        assert!(ThreadPoolBuilder::new().workers(0).build().is_err());
+        assert!(ThreadPoolBuilder::new()
+            .min_workers(4)
+            .max_workers(2)
+            .build()
+            .is_err());
+        assert!(ThreadPoolBuilder::new().queue_capacity(0).build().is_err());
+    }
+
+    #[test]
+    fn test_submit_returns_value() {
+        let pool = ThreadPool::new(2);
+        let handle = pool.submit(|| 6 * 7).unwrap();
+
+        assert_eq!(42, handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_submit_captures_panic() {
+        let pool = ThreadPool::new(1);
+        let handle = pool.submit(|| -> usize { panic!("boom") }).unwrap();
+
+        let error = handle.join().unwrap_err();
+        assert_eq!(Some("boom"), error.panic_message());
+        assert_eq!("Job panicked: boom", error.to_string());
+
+        assert_eq!(1, pool.submit(|| 1).unwrap().join().unwrap());
+    }
+
+    #[test]
+    fn test_join_all() {
+        let pool = ThreadPool::new(3);
+        let handles = (0..10)
+            .map(|i| {
+                pool.submit(move || {
+                    if i == 5 {
+                        panic!("five");
                     }
-                }
-                Err(_) => {
-                    println!("Worker {id} disconnected; shutting down.");
-                    break;
+                    i * 2
+                })
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let results = join_all(handles);
+        assert_eq!(10, results.len());
+        for (i, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(value) => assert_eq!(i * 2, value),
+                Err(error) => {
+                    assert_eq!(5, i);
+                    assert_eq!(Some("five"), error.panic_message());
                 }
             }
+        }
+    }
+
+    #[test]
+    fn test_idle_workers_steal_nested_jobs() {
+        let pool = Arc::new(ThreadPool::new(3));
+        let (sender, receiver) = mpsc::channel();
+
+        let inner_pool = Arc::clone(&pool);
+        let handle = pool
+            .submit(panic::AssertUnwindSafe(move || {
+                let handles = (0..20)
+                    .map(|i| inner_pool.submit(move || i).unwrap())
+                    .collect::<Vec<_>>();
+                // Blocks this worker, so its local jobs can only complete by being stolen.
+                let sum = join_all(handles)
+                    .into_iter()
+                    .map(Result::unwrap)
+                    .sum::<usize>();
+                sender.send(sum).unwrap();
+            }))
+            .unwrap();
+
+        assert_eq!(190, receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_scope_borrows_local_data() {
+        let pool = ThreadPool::new(3);
+        let mut numbers = vec![1, 2, 3, 4, 5, 6];
+        let total = AtomicUsize::new(0);
+
+        pool.scope(|scope| {
+            for chunk in numbers.chunks_mut(2) {
+                let total = &total;
+                scope
+                    .execute(move || {
+                        for number in chunk.iter_mut() {
+                            *number *= 10;
+                            total.fetch_add(*number, Ordering::SeqCst);
+                        }
+                    })
+                    .unwrap();
+            }
         });
 
-        Self {
-            id,
-            thread: Some(thread),
+        assert_eq!(vec![10, 20, 30, 40, 50, 60], numbers);
+        assert_eq!(210, total.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_scope_waits_for_jobs_and_returns_value() {
+        let pool = ThreadPool::new(2);
+        let finished = AtomicUsize::new(0);
+
+        let value = pool.scope(|scope| {
+            for _ in 0..4 {
+                scope
+                    .execute(|| {
+                        thread::sleep(Duration::from_millis(20));
+                        finished.fetch_add(1, Ordering::SeqCst);
+                    })
+                    .unwrap();
+            }
+            "done"
+        });
+
+        assert_eq!("done", value);
+        assert_eq!(4, finished.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_scope_reraises_job_panic() {
+        let pool = ThreadPool::new(2);
+        let finished = AtomicUsize::new(0);
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            pool.scope(|scope| {
+                scope.execute(|| panic!("scoped boom")).unwrap();
+                scope
+                    .execute(|| {
+                        thread::sleep(Duration::from_millis(20));
+                        finished.fetch_add(1, Ordering::SeqCst);
+                    })
+                    .unwrap();
+            })
+        }));
+
+        let payload = result.unwrap_err();
+        assert_eq!(Some(&"scoped boom"), payload.downcast_ref::<&str>());
+        assert_eq!(1, finished.load(Ordering::SeqCst));
+        assert_eq!(2, pool.submit(|| 2).unwrap().join().unwrap());
+    }
+
+    #[test]
+    fn test_nested_scope_inside_job() {
+        let pool = Arc::new(ThreadPool::new(1));
+        let inner_pool = Arc::clone(&pool);
+
+        let handle = pool
+            .submit(panic::AssertUnwindSafe(move || {
+                let total = AtomicUsize::new(0);
+                inner_pool.scope(|scope| {
+                    for i in 1..=3 {
+                        let total = &total;
+                        scope
+                            .execute(move || {
+                                total.fetch_add(i, Ordering::SeqCst);
+                            })
+                            .unwrap();
+                    }
+                });
+                total.into_inner()
+            }))
+            .unwrap();
+
+        assert_eq!(6, handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_schedule_runs_after_delay() {
+        let pool = ThreadPool::new(2);
+        let (sender, receiver) = mpsc::channel();
+        let scheduled_at = Instant::now();
+
+        pool.schedule(Duration::from_millis(50), move || {
+            sender.send(Instant::now()).unwrap();
+        })
+        .unwrap();
+
+        let ran_at = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(ran_at - scheduled_at >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_cancel_scheduled_job() {
+        let pool = ThreadPool::new(1);
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&ran);
+        let handle = pool
+            .schedule(Duration::from_millis(50), move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        handle.cancel();
+        assert!(handle.is_cancelled());
+
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(0, ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_schedule_interval_until_cancelled() {
+        let pool = ThreadPool::new(2);
+        let ticks = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&ticks);
+        let handle = pool
+            .schedule_interval(Duration::from_millis(20), move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        let started = Instant::now();
+        while ticks.load(Ordering::SeqCst) < 3 && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(5));
         }
+        handle.cancel();
+        thread::sleep(Duration::from_millis(50));
+
+        let after_cancel = ticks.load(Ordering::SeqCst);
+        assert!(after_cancel >= 3);
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(after_cancel, ticks.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_shutdown_stops_scheduled_jobs() {
+        let mut pool = ThreadPool::new(1);
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&ran);
+        pool.schedule_interval(Duration::from_millis(10), move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        assert_eq!(0, pool.shutdown_timeout(Duration::from_secs(1)));
+        let after_shutdown = ran.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(after_shutdown, ran.load(Ordering::SeqCst));
+        assert_eq!(
+            Some(ExecuteError::ShutDown),
+            pool.schedule(Duration::ZERO, || {}).err()
+        );
+    }
+
+    #[test]
+    fn test_panic_handler_receives_worker_and_payload() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let pool = ThreadPoolBuilder::new()
+            .workers(1)
+            .panic_handler(move |id, payload| {
+                let message = payload.downcast_ref::<&str>().copied().unwrap_or_default();
+                sender
+                    .lock()
+                    .unwrap()
+                    .send((id, message.to_owned()))
+                    .unwrap();
+            })
+            .build()
+            .unwrap();
+
+        pool.execute(|| panic!("handled")).unwrap();
+
+        assert_eq!(
+            (0, String::from("handled")),
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_worker_stats() {
+        let pool = ThreadPool::new(1);
+        for i in 0..4 {
+            pool.submit(move || {
+                if i % 2 == 0 {
+                    panic!("even");
+                }
+            })
+            .unwrap()
+            .join()
+            .ok();
+        }
+        pool.execute(|| panic!("counted")).unwrap();
+        pool.submit(|| {}).unwrap().join().unwrap();
+
+        let started = Instant::now();
+        while pool.worker_stats()[0].jobs_run < 6 && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(
+            vec![WorkerStats {
+                id: 0,
+                jobs_run: 6,
+                panics: 1,
+            }],
+            pool.worker_stats()
+        );
+    }
+
+    #[test]
+    fn test_respawns_worker_killed_by_panic_handler() {
+        let pool = ThreadPoolBuilder::new()
+            .name_prefix("respawn")
+            .workers(1)
+            .panic_handler(|_, _| panic!("bad handler"))
+            .build()
+            .unwrap();
+
+        pool.execute(|| panic!("job")).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(move || {
+            sender
+                .send(thread::current().name().map(str::to_owned))
+                .unwrap();
+        })
+        .unwrap();
+
+        assert_eq!(
+            Some(String::from("respawn-1")),
+            receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+        );
+        assert_eq!(1, pool.size());
     }
 }
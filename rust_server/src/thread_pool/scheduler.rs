@@ -0,0 +1,229 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    io,
+    panic::RefUnwindSafe,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::{ExecuteError, Job, Shared};
+
+pub(super) struct Scheduler {
+    state: Arc<SchedulerState>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    queue: Mutex<TimerQueue>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct TimerQueue {
+    entries: BinaryHeap<Reverse<Entry>>,
+    next_sequence: u64,
+    shutting_down: bool,
+}
+
+struct Entry {
+    deadline: Instant,
+    sequence: u64,
+    cancelled: Arc<AtomicBool>,
+    task: Task,
+}
+
+enum Task {
+    Once(Job),
+    Repeating {
+        job: Arc<dyn Fn() + Send + Sync + RefUnwindSafe>,
+        interval: Duration,
+        running: Arc<AtomicBool>,
+    },
+}
+
+impl Scheduler {
+    pub(super) fn new(shared: Arc<Shared>) -> io::Result<Self> {
+        let state = Arc::new(SchedulerState::default());
+        let thread_state = Arc::clone(&state);
+
+        let thread = thread::Builder::new()
+            .name(format!("{}-scheduler", shared.config.name_prefix))
+            .spawn(move || run(&thread_state, &shared))?;
+
+        Ok(Self {
+            state,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    pub(super) fn schedule_once(
+        &self,
+        delay: Duration,
+        job: Job,
+    ) -> Result<ScheduledHandle, ExecuteError> {
+        self.insert(Instant::now() + delay, Task::Once(job))
+    }
+
+    pub(super) fn schedule_repeating(
+        &self,
+        interval: Duration,
+        job: Arc<dyn Fn() + Send + Sync + RefUnwindSafe>,
+    ) -> Result<ScheduledHandle, ExecuteError> {
+        let task = Task::Repeating {
+            job,
+            interval,
+            running: Arc::default(),
+        };
+        self.insert(Instant::now() + interval, task)
+    }
+
+    pub(super) fn shut_down(&self) {
+        let mut queue = self.state.queue.lock().unwrap();
+        queue.shutting_down = true;
+        queue.entries.clear();
+        self.state.changed.notify_all();
+    }
+
+    pub(super) fn join(&self) {
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            thread.join().unwrap();
+        }
+    }
+
+    fn insert(&self, deadline: Instant, task: Task) -> Result<ScheduledHandle, ExecuteError> {
+        let mut queue = self.state.queue.lock().unwrap();
+        if queue.shutting_down {
+            return Err(ExecuteError::ShutDown);
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let sequence = queue.next_sequence;
+        queue.next_sequence += 1;
+        queue.entries.push(Reverse(Entry {
+            deadline,
+            sequence,
+            cancelled: Arc::clone(&cancelled),
+            task,
+        }));
+        self.state.changed.notify_one();
+
+        Ok(ScheduledHandle { cancelled })
+    }
+}
+
+impl ScheduledHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, atomic::Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(atomic::Ordering::SeqCst)
+    }
+}
+
+fn run(state: &SchedulerState, shared: &Arc<Shared>) {
+    let mut queue = state.queue.lock().unwrap();
+
+    loop {
+        if queue.shutting_down {
+            break;
+        }
+
+        let now = Instant::now();
+        let deadline = match queue.entries.peek() {
+            None => {
+                queue = state.changed.wait(queue).unwrap();
+                continue;
+            }
+            Some(Reverse(entry)) => entry.deadline,
+        };
+        if deadline > now {
+            queue = state.changed.wait_timeout(queue, deadline - now).unwrap().0;
+            continue;
+        }
+
+        let Some(Reverse(entry)) = queue.entries.pop() else {
+            continue;
+        };
+        if entry.cancelled.load(atomic::Ordering::SeqCst) {
+            continue;
+        }
+
+        let job = match entry.task {
+            Task::Once(job) => job,
+            Task::Repeating {
+                job,
+                interval,
+                running,
+            } => {
+                let next_deadline = (entry.deadline + interval).max(now);
+                let sequence = queue.next_sequence;
+                queue.next_sequence += 1;
+                queue.entries.push(Reverse(Entry {
+                    deadline: next_deadline,
+                    sequence,
+                    cancelled: entry.cancelled,
+                    task: Task::Repeating {
+                        job: Arc::clone(&job),
+                        interval,
+                        running: Arc::clone(&running),
+                    },
+                }));
+
+                // Skip this tick rather than overlap a run that is still in progress.
+                if running.swap(true, atomic::Ordering::SeqCst) {
+                    continue;
+                }
+                Box::new(move || {
+                    let _running = RunningGuard(running);
+                    job();
+                })
+            }
+        };
+
+        drop(queue);
+        if let Err(e) = shared.execute(job) {
+            eprintln!("Scheduled job was not run: {e}");
+        }
+        queue = state.queue.lock().unwrap();
+    }
+}
+
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, atomic::Ordering::SeqCst);
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deadline, self.sequence).cmp(&(other.deadline, other.sequence))
+    }
+}
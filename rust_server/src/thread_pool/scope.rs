@@ -0,0 +1,120 @@
+use std::{
+    any::Any,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe, UnwindSafe},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use super::{worker, ExecuteError, Job, Shared, ThreadPool};
+
+const HELP_INTERVAL: Duration = Duration::from_millis(10);
+
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    state: Arc<ScopeState>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    pending: Mutex<usize>,
+    all_done: Condvar,
+    panics: Mutex<Vec<Box<dyn Any + Send + 'static>>>,
+}
+
+struct ScopedJob<F> {
+    f: F,
+    pending: PendingGuard,
+}
+
+struct PendingGuard(Arc<ScopeState>);
+
+type ScopedBox<'scope> = Box<dyn FnOnce() + Send + UnwindSafe + 'scope>;
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    pub(super) fn new(pool: &'scope ThreadPool) -> Self {
+        Self {
+            pool,
+            state: Arc::default(),
+            scope: PhantomData,
+            env: PhantomData,
+        }
+    }
+
+    pub fn execute<F>(&'scope self, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        *self.state.pending.lock().unwrap() += 1;
+        let job = ScopedJob {
+            f,
+            pending: PendingGuard(Arc::clone(&self.state)),
+        };
+
+        let job: ScopedBox<'scope> = Box::new(AssertUnwindSafe(move || job.run()));
+        // SAFETY: `ThreadPool::scope` does not return until every job has run or been
+        // dropped, so nothing borrowed for 'scope is used after it ends.
+        let job = unsafe { mem::transmute::<ScopedBox<'scope>, Job>(job) };
+
+        self.pool.execute_job(job)
+    }
+
+    pub(super) fn wait(&self, shared: &Shared) {
+        loop {
+            if *self.state.pending.lock().unwrap() == 0 {
+                return;
+            }
+            // A scope opened from one of the pool's own workers helps run jobs
+            // rather than blocking a thread the scoped jobs may need.
+            if worker::run_pending_job(shared) {
+                continue;
+            }
+
+            let pending = self.state.pending.lock().unwrap();
+            if *pending == 0 {
+                return;
+            }
+            if worker::is_worker_of(shared) {
+                drop(
+                    self.state
+                        .all_done
+                        .wait_timeout(pending, HELP_INTERVAL)
+                        .unwrap(),
+                );
+            } else {
+                drop(self.state.all_done.wait(pending).unwrap());
+            }
+        }
+    }
+
+    pub(super) fn take_panic(&self) -> Option<Box<dyn Any + Send + 'static>> {
+        let mut panics = self.state.panics.lock().unwrap();
+        if panics.is_empty() {
+            None
+        } else {
+            Some(panics.swap_remove(0))
+        }
+    }
+}
+
+impl<F: FnOnce()> ScopedJob<F> {
+    fn run(self) {
+        let Self { f, pending } = self;
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+            pending.0.panics.lock().unwrap().push(payload);
+        }
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        let mut pending = self.0.pending.lock().unwrap();
+        *pending -= 1;
+        if *pending == 0 {
+            self.0.all_done.notify_all();
+        }
+    }
+}
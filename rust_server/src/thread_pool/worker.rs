@@ -0,0 +1,274 @@
+use std::{
+    cell::RefCell,
+    io, iter, panic,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crossbeam_deque::{Steal, Stealer, Worker as Deque};
+
+use super::{Job, Shared, Wakeup, WorkerStats};
+
+thread_local! {
+    static LOCAL_QUEUE: RefCell<Option<LocalQueue>> = const { RefCell::new(None) };
+}
+
+struct LocalQueue {
+    id: usize,
+    pool: *const Shared,
+    deque: Deque<Job>,
+    counters: Arc<WorkerCounters>,
+    stealers: RefCell<StealerSnapshot>,
+}
+
+// A private copy of the other workers' stealers, refreshed only when a worker
+// starts or stops, so stealing doesn't contend on the shared lock.
+struct StealerSnapshot {
+    generation: usize,
+    stealers: Vec<Stealer<Job>>,
+}
+
+pub(super) struct Worker {
+    pub(super) id: usize,
+    thread: Option<JoinHandle<()>>,
+    counters: Arc<WorkerCounters>,
+}
+
+#[derive(Default)]
+struct WorkerCounters {
+    jobs_run: AtomicU64,
+    panics: AtomicU64,
+}
+
+struct Sentinel<'a> {
+    id: usize,
+    shared: &'a Arc<Shared>,
+}
+
+impl Worker {
+    pub(super) fn new(id: usize, shared: Arc<Shared>) -> io::Result<Self> {
+        let mut builder =
+            thread::Builder::new().name(format!("{}-{id}", shared.config.name_prefix));
+        if let Some(stack_size) = shared.config.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        let counters = Arc::new(WorkerCounters::default());
+        let worker_counters = Arc::clone(&counters);
+        let thread = builder.spawn(move || run(id, &shared, worker_counters))?;
+
+        Ok(Self {
+            id,
+            thread: Some(thread),
+            counters,
+        })
+    }
+
+    pub(super) fn stats(&self) -> WorkerStats {
+        WorkerStats {
+            id: self.id,
+            jobs_run: self.counters.jobs_run.load(Ordering::Relaxed),
+            panics: self.counters.panics.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(super) fn is_running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    pub(super) fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| thread.is_finished())
+    }
+
+    pub(super) fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            // A worker that died has already been reported and replaced.
+            let _ = thread.join();
+        }
+    }
+
+    pub(super) fn detach(&mut self) -> bool {
+        self.thread.take().is_some()
+    }
+}
+
+pub(super) fn push_local(shared: &Shared, job: Job) -> Option<Job> {
+    LOCAL_QUEUE.with(|local| match &*local.borrow() {
+        Some(queue) if std::ptr::eq(queue.pool, shared) => {
+            queue.deque.push(job);
+            None
+        }
+        _ => Some(job),
+    })
+}
+
+pub(super) fn is_worker_of(shared: &Shared) -> bool {
+    local_worker_id(shared).is_some()
+}
+
+pub(super) fn run_pending_job(shared: &Shared) -> bool {
+    let Some((id, counters)) = local_worker(shared) else {
+        return false;
+    };
+    let Some(job) = find_job(shared) else {
+        return false;
+    };
+
+    run_job(id, shared, &counters, job);
+    true
+}
+
+fn local_worker(shared: &Shared) -> Option<(usize, Arc<WorkerCounters>)> {
+    LOCAL_QUEUE.with(|local| match &*local.borrow() {
+        Some(queue) if std::ptr::eq(queue.pool, shared) => {
+            Some((queue.id, Arc::clone(&queue.counters)))
+        }
+        _ => None,
+    })
+}
+
+fn local_worker_id(shared: &Shared) -> Option<usize> {
+    local_worker(shared).map(|(id, _)| id)
+}
+
+fn run_job(id: usize, shared: &Shared, counters: &WorkerCounters, job: Job) {
+    shared.job_taken();
+    let job_result = panic::catch_unwind(job);
+    counters.jobs_run.fetch_add(1, Ordering::Relaxed);
+
+    if let Err(payload) = job_result {
+        counters.panics.fetch_add(1, Ordering::Relaxed);
+        match &shared.config.panic_handler {
+            Some(handler) => handler(id, payload.as_ref()),
+            None => eprintln!("Job on Worker {id} panicked!"),
+        }
+    }
+}
+
+fn run(id: usize, shared: &Arc<Shared>, counters: Arc<WorkerCounters>) {
+    let _sentinel = Sentinel { id, shared };
+    let deque = Deque::new_fifo();
+    shared.update_stealers(|stealers| stealers.push((id, deque.stealer())));
+    LOCAL_QUEUE.with(|local| {
+        *local.borrow_mut() = Some(LocalQueue {
+            id,
+            pool: Arc::as_ptr(shared),
+            deque,
+            counters: Arc::clone(&counters),
+            stealers: RefCell::new(StealerSnapshot {
+                generation: usize::MAX,
+                stealers: Vec::new(),
+            }),
+        })
+    });
+
+    // Only the idle/busy transitions touch the shared counter, not every job.
+    shared.idle.fetch_add(1, Ordering::SeqCst);
+    let mut idle = true;
+    loop {
+        match find_job(shared) {
+            Some(job) => {
+                if idle {
+                    idle = false;
+                    shared.idle.fetch_sub(1, Ordering::SeqCst);
+                    // This worker stops looking, so pass any remaining work on.
+                    if shared.queued.load(Ordering::SeqCst) > 1 {
+                        shared.wake_worker();
+                    }
+                }
+                run_job(id, shared, &counters, job);
+            }
+            // Searched once more after going idle so a push that saw this
+            // worker as busy is still picked up.
+            None if !idle => {
+                idle = true;
+                shared.idle.fetch_add(1, Ordering::SeqCst);
+            }
+            None if shared.is_shutting_down() => {
+                if shared.queued.load(Ordering::SeqCst) == 0 {
+                    shared.live.fetch_sub(1, Ordering::SeqCst);
+                    break;
+                }
+                thread::yield_now();
+            }
+            None => {
+                if let Wakeup::TimedOut = shared.wait_for_job() {
+                    if shared.retire_idle_worker() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    shared.idle.fetch_sub(1, Ordering::SeqCst);
+}
+
+impl Drop for Sentinel<'_> {
+    fn drop(&mut self) {
+        let shared = self.shared;
+
+        // Jobs this worker queued locally must not be lost with its deque.
+        if let Some(queue) = LOCAL_QUEUE.with(|local| local.borrow_mut().take()) {
+            while let Some(job) = queue.deque.pop() {
+                shared.injector.push(job);
+            }
+        }
+        shared.update_stealers(|stealers| stealers.retain(|(worker_id, _)| *worker_id != self.id));
+
+        if !thread::panicking() {
+            return;
+        }
+
+        eprintln!("Worker {} died unexpectedly; respawning.", self.id);
+        if shared.is_shutting_down() {
+            shared.live.fetch_sub(1, Ordering::SeqCst);
+        } else if let Err(e) = shared.spawn_worker() {
+            shared.live.fetch_sub(1, Ordering::SeqCst);
+            eprintln!("Failed to respawn worker: {e}");
+        }
+    }
+}
+
+fn find_job(shared: &Shared) -> Option<Job> {
+    LOCAL_QUEUE.with(|local| {
+        let local = local.borrow();
+        let queue = local.as_ref()?;
+
+        queue.deque.pop().or_else(|| {
+            iter::repeat_with(|| {
+                shared
+                    .injector
+                    .steal_batch_and_pop(&queue.deque)
+                    .or_else(|| queue.steal(shared))
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(Steal::success)
+        })
+    })
+}
+
+impl LocalQueue {
+    fn steal(&self, shared: &Shared) -> Steal<Job> {
+        let mut snapshot = self.stealers.borrow_mut();
+        let generation = shared.stealers_generation.load(Ordering::Acquire);
+        if snapshot.generation != generation {
+            snapshot.generation = generation;
+            snapshot.stealers = shared
+                .stealers
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|(worker_id, _)| *worker_id != self.id)
+                .map(|(_, stealer)| stealer.clone())
+                .collect();
+        }
+
+        snapshot.stealers.iter().map(Stealer::steal).collect()
+    }
+}